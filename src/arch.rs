@@ -0,0 +1,53 @@
+use std::str::FromStr;
+use std::fmt;
+
+/// Architecture levels, each one adding instructions to the previous ones
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Arch {
+    V4,
+    /// Thumb interworking with BX
+    V4T,
+    V5T,
+    /// DSP extensions
+    V5TE,
+    V6,
+    /// Multiprocessing extensions
+    V6K,
+    V6T2,
+    #[default]
+    V7A,
+}
+
+impl FromStr for Arch {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_ref() {
+            "armv4"   => Ok(Self::V4),
+            "armv4t"  => Ok(Self::V4T),
+            "armv5t"  => Ok(Self::V5T),
+            "armv5te" => Ok(Self::V5TE),
+            "armv6"   => Ok(Self::V6),
+            "armv6k"  => Ok(Self::V6K),
+            "armv6t2" => Ok(Self::V6T2),
+            "armv7-a" => Ok(Self::V7A),
+            _         => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for Arch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Self::V4   => "ARMv4",
+            Self::V4T  => "ARMv4T",
+            Self::V5T  => "ARMv5T",
+            Self::V5TE => "ARMv5TE",
+            Self::V6   => "ARMv6",
+            Self::V6K  => "ARMv6K",
+            Self::V6T2 => "ARMv6T2",
+            Self::V7A  => "ARMv7-A",
+        };
+        write!(f, "{}", name)
+    }
+}
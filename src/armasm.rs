@@ -0,0 +1,475 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use crate::register::{Register, CoRegister};
+use crate::shift::Shift;
+use crate::psrf::PSRF;
+use crate::cpn::CPN;
+
+/// Number of nested macro expansions after which the front end gives up
+const MAX_MACRO_DEPTH: usize = 64;
+
+/// Macro defined between `MACRO` and `MEND`
+struct Macro {
+    /// Parameter receiving the label of the invocation, such as `$label`
+    label: Option<String>,
+    /// Parameters, along with their default value
+    params: Vec<(String, Option<String>)>,
+    /// Lines of the macro, before substitution
+    body: Vec<String>,
+}
+
+/// Front end for the syntax of the ARM toolchain (armasm). The source is
+/// translated line by line into the syntax understood by the lexer, so that
+/// the statements go through the same assembler and instruction parsers.
+#[derive(Default)]
+pub struct Armasm {
+    macros: HashMap<String, Macro>,
+    /// Translated source
+    pub output: String,
+    /// Line of the original source that each translated line comes from
+    pub lines: Vec<usize>,
+}
+
+impl Armasm {
+    /// Translate `source` into `output`
+    pub fn translate(&mut self, source: &str) {
+        let mut lines = source.lines().enumerate();
+
+        while let Some((n, line)) = lines.next() {
+            let (_, op, _) = Self::split(line);
+            match op.eq_ignore_ascii_case("MACRO") {
+                true => self.define_macro(&mut lines),
+                false => self.line(line, n, 0),
+            }
+        }
+    }
+
+    /// Append a translated line, coming from the source line `n`
+    fn push(&mut self, line: &str, n: usize) {
+        self.output.push_str(line);
+        self.output.push('\n');
+        self.lines.push(n);
+    }
+
+    /// Remove the comment of a line, starting at ';' outside of quotes
+    fn strip_comment(line: &str) -> &str {
+        let mut quote = None;
+        for (i, chr) in line.char_indices() {
+            match (quote, chr) {
+                (None, ';') => return &line[..i],
+                (None, '"' | '\'') => quote = Some(chr),
+                (Some(q), _) if chr == q => quote = None,
+                _ => {},
+            }
+        }
+        line
+    }
+
+    /// Split a line into its label, which starts in the first column, its
+    /// operation and its operands
+    fn split(line: &str) -> (&str, &str, &str) {
+        let line = Self::strip_comment(line).trim_end();
+
+        let (label, rest) = match line.starts_with(char::is_whitespace) {
+            true => ("", line.trim_start()),
+            false => line.split_once(char::is_whitespace)
+                .map_or((line, ""), |(label, rest)| (label, rest.trim_start())),
+        };
+        let (op, operands) = rest.split_once(char::is_whitespace)
+            .map_or((rest, ""), |(op, operands)| (op, operands.trim_start()));
+
+        (label, op, operands)
+    }
+
+    /// Record the macro whose prototype is on the next line, up to `MEND`:
+    /// {$label} name {$param{=default}{,$param{=default}}}
+    fn define_macro<'s>(&mut self,
+        lines: &mut impl Iterator<Item = (usize, &'s str)>) {
+        let (_, prototype) = lines.next().expect("Missing macro prototype.");
+        let (label, name, params) = Self::split(prototype);
+
+        let label = match label {
+            "" => None,
+            label => Some(label.to_string()),
+        };
+        let params = Self::split_operands(params).into_iter()
+            .map(|param| match param.split_once('=') {
+                Some((param, default)) => (param.to_string(),
+                    Some(default.to_string())),
+                None => (param.to_string(), None),
+            })
+            .collect();
+
+        let mut body = Vec::new();
+        for (_, line) in lines.by_ref() {
+            let (_, op, _) = Self::split(line);
+            if op.eq_ignore_ascii_case("MEND") {
+                self.macros.insert(name.to_string(), Macro { label, params,
+                    body });
+                return;
+            }
+            body.push(line.to_string());
+        }
+
+        panic!("Missing MEND at the end of macro {}.", name);
+    }
+
+    /// Split a list of operands on the commas found outside of quotes,
+    /// brackets and braces
+    fn split_operands(operands: &str) -> Vec<&str> {
+        let mut list = Vec::new();
+        let mut depth = 0;
+        let mut quote = None;
+        let mut start = 0;
+
+        for (i, chr) in operands.char_indices() {
+            match (quote, chr) {
+                (Some(q), _) if chr == q => quote = None,
+                (Some(_), _) => {},
+                (None, '"' | '\'') => quote = Some(chr),
+                (None, '[' | '{' | '(') => depth += 1,
+                (None, ']' | '}' | ')') => depth -= 1,
+                (None, ',') if depth == 0 => {
+                    list.push(operands[start..i].trim());
+                    start = i + 1;
+                },
+                _ => {},
+            }
+        }
+
+        let last = operands[start..].trim();
+        if !last.is_empty() || !list.is_empty() {
+            list.push(last);
+        }
+        list
+    }
+
+    /// Translate the source line `n`, or a line of a macro expanded `depth`
+    /// times
+    fn line(&mut self, line: &str, n: usize, depth: usize) {
+        let (label, op, operands) = Self::split(line);
+
+        if let Some(mac) = self.macros.get(op) {
+            if depth == MAX_MACRO_DEPTH {
+                panic!("Macro {} expanded too many times.", op);
+            }
+
+            let body = Self::expand(mac, label, operands);
+            // A label not taken by a parameter marks the first line
+            if mac.label.is_none() && !label.is_empty() {
+                self.push(&Self::label(label), n);
+            }
+            for line in body {
+                self.line(&line, n, depth + 1);
+            }
+            return;
+        }
+
+        let translated = match op.to_ascii_uppercase().as_str() {
+            // The label is the name of the symbol
+            "EQU" | "*" => format!(".equ {}, {}",
+                Self::operands(label, false), Self::operands(operands, false)),
+            "AREA" => return self.area(label, operands, n),
+            "ENTRY" | "ARM" | "CODE32" | "PRESERVE8" | "REQUIRE8" => {
+                Self::label(label)
+            },
+            "THUMB" | "CODE16" => panic!("Thumb code is not supported."),
+            "END" => format!("{} .end", Self::label(label)),
+            "LTORG" => format!("{} .ltorg", Self::label(label)),
+            "DCD" | "DCDU" => Self::data(label, ".word", operands),
+            "DCW" | "DCWU" => Self::data(label, ".hword", operands),
+            "DCB" => Self::data(label, ".byte", operands),
+            "SPACE" | "%" => Self::data(label, ".space", operands),
+            "FILL" => {
+                // FILL size{,value{,value size}}
+                match Self::split_operands(operands).as_slice() {
+                    [_, _, size] if size.trim() != "1" => {
+                        panic!("Only bytes are supported by FILL.")
+                    },
+                    [size, value, ..] => Self::data(label, ".space",
+                        &format!("{}, {}", size, value)),
+                    _ => Self::data(label, ".space", operands),
+                }
+            },
+            "ALIGN" => Self::data(label, ".balign", operands),
+            "EXPORT" | "GLOBAL" => Self::data(label, ".global", operands),
+            "IMPORT" | "EXTERN" => Self::data(label, ".extern", operands),
+            "IF" | "[" => format!(".if {}", Self::operands(operands, true)),
+            "ELIF" => format!(".elseif {}", Self::operands(operands, true)),
+            "ELSE" | "|" => ".else".to_string(),
+            "ENDIF" | "]" => ".endif".to_string(),
+            "MEND" => panic!("MEND without matching MACRO."),
+            "" => Self::label(label),
+            // Instructions, written in lowercase
+            _ => format!("{} {} {}", Self::label(label),
+                op.to_ascii_lowercase(), Self::operands(operands, false)),
+        };
+
+        self.push(translated.trim(), n);
+    }
+
+    /// Return the definition of `label`, if any
+    fn label(label: &str) -> String {
+        match label {
+            "" => String::new(),
+            label => format!("{}:", Self::operands(label, false)),
+        }
+    }
+
+    /// Translate a data directive, along with its label
+    fn data(label: &str, directive: &str, operands: &str) -> String {
+        format!("{} {} {}", Self::label(label), directive,
+            Self::operands(operands, false))
+    }
+
+    /// Translate `AREA name{,attr}{,attr}...`, which starts a section. The
+    /// attributes give the flags of the section, which is read-only for CODE
+    /// and writable for DATA unless told otherwise.
+    fn area(&mut self, label: &str, operands: &str, n: usize) {
+        let operands = Self::split_operands(operands);
+        let name = operands.first().expect("Expected an area name.");
+        let name = name.trim_matches('|');
+
+        let (mut code, mut data, mut noinit) = (false, false, false);
+        let (mut readonly, mut readwrite) = (false, false);
+        let mut align = None;
+        for attr in &operands[1..] {
+            let attr = attr.trim();
+            match attr.to_ascii_uppercase().as_str() {
+                "CODE" => code = true,
+                "DATA" => data = true,
+                "NOINIT" => noinit = true,
+                "READONLY" => readonly = true,
+                "READWRITE" => readwrite = true,
+                // Alignment given as a power of 2
+                key if key.starts_with("ALIGN") => {
+                    match attr.split_once('=') {
+                        Some((_, value)) => align = Some(value.trim()),
+                        None => panic!("Expected ALIGN=<expression>."),
+                    }
+                },
+                _ => {},
+            }
+        }
+
+        let flags = match (code, data || noinit) {
+            (true, true) => panic!("An area cannot be both CODE and DATA."),
+            (true, false) if readwrite => Some("awx"),
+            (true, false) => Some("ax"),
+            (false, true) if readonly => Some("a"),
+            (false, true) => Some("aw"),
+            // Guessed from the name by the assembler
+            (false, false) => None,
+        };
+        let section = match (flags, noinit) {
+            (Some(flags), true) => format!("{}, \"{}\", %nobits", name, flags),
+            (Some(flags), false) => format!("{}, \"{}\"", name, flags),
+            (None, _) => name.to_string(),
+        };
+
+        self.push(format!("{} .section {}", Self::label(label), section)
+            .trim(), n);
+        if let Some(align) = align {
+            self.push(&format!(".align {}", align), n);
+        }
+    }
+
+    /// Return the lines of the macro `mac` invoked with `operands`
+    fn expand(mac: &Macro, label: &str, operands: &str) -> Vec<String> {
+        let args = Self::split_operands(operands);
+        if args.len() > mac.params.len() {
+            panic!("Too many arguments given to a macro.");
+        }
+
+        let mut values: Vec<(&str, String)> = mac.params.iter().enumerate()
+            .map(|(i, (param, default))| {
+                let value = match args.get(i) {
+                    // An empty argument or '|' takes the default value
+                    Some(&arg) if !arg.is_empty() && arg != "|" => {
+                        arg.to_string()
+                    },
+                    _ => default.clone().unwrap_or_default(),
+                };
+                (param.as_str(), value)
+            })
+            .collect();
+        if let Some(param) = &mac.label {
+            values.push((param, label.to_string()));
+        }
+
+        // Substitute the longest names first, so that a parameter is not
+        // replaced by another one starting with the same name
+        values.sort_by_key(|(param, _)| std::cmp::Reverse(param.len()));
+
+        mac.body.iter().map(|line| {
+            let mut line = line.clone();
+            for (param, value) in &values {
+                // A dot after the name joins it with the following text
+                line = line.replace(&format!("{}.", param), value)
+                    .replace(param, value);
+            }
+            line
+        }).collect()
+    }
+
+    /// Return true if `word` names a register, a shift, a PSR or a
+    /// coprocessor, which are written in lowercase
+    fn is_reserved(word: &str) -> bool {
+        Register::from_str(word).is_ok()
+            || CoRegister::from_str(word).is_ok()
+            || Shift::from_str(word).is_ok()
+            || PSRF::from_str(word).is_ok()
+            || CPN::from_str(word).is_ok()
+    }
+
+    /// Translate the operands of a statement: operators written as `:OP:`,
+    /// hex numbers written as `&ff`, numbers in base n written as `n_digits`,
+    /// names between bars, `{PC}`, numeric label references and strings. In
+    /// conditions, `=` and `<>` are comparisons.
+    fn operands(operands: &str, condition: bool) -> String {
+        let chars: Vec<char> = operands.chars().collect();
+        let mut out = String::new();
+
+        let mut i = 0;
+        while i < chars.len() {
+            let chr = chars[i];
+
+            // Length of the text starting at `i` made of the chars matching
+            // `f`
+            let span = |start: usize, f: fn(&char) -> bool| chars[start..]
+                .iter().take_while(|chr| f(chr)).count();
+
+            match chr {
+                '"' => {
+                    // String, turned into a list of characters
+                    let len = chars[i + 1..].iter()
+                        .position(|&chr| chr == '"')
+                        .expect("Missing closing quote.");
+                    let codes: Vec<String> = chars[i + 1..i + 1 + len].iter()
+                        .map(|&chr| (chr as u32).to_string())
+                        .collect();
+                    out.push_str(&codes.join(", "));
+                    i += len + 2;
+                },
+                '\'' => {
+                    // Character constant, copied as is
+                    let len = chars[i + 1..].iter()
+                        .position(|&chr| chr == '\'')
+                        .map_or(chars.len() - i, |len| len + 2);
+                    out.extend(&chars[i..i + len]);
+                    i += len;
+                },
+                ':' => {
+                    let len = span(i + 1, char::is_ascii_alphabetic);
+                    let op = chars[i + 1..i + 1 + len].iter()
+                        .collect::<String>().to_ascii_uppercase();
+                    if chars.get(i + 1 + len) != Some(&':') {
+                        panic!("Invalid operator in {}.", operands);
+                    }
+                    out.push_str(match op.as_str() {
+                        "AND"  => " & ",
+                        "OR"   => " | ",
+                        "EOR"  => " ^ ",
+                        "SHL"  => " << ",
+                        "SHR"  => " >> ",
+                        "MOD"  => " % ",
+                        "NOT"  => " ~",
+                        "LAND" => " && ",
+                        "LOR"  => " || ",
+                        "LNOT" => " !",
+                        _      => panic!("Unknown operator :{}:.", op),
+                    });
+                    i += len + 2;
+                },
+                '|' => {
+                    // Name between bars
+                    let len = chars[i + 1..].iter()
+                        .position(|&chr| chr == '|')
+                        .expect("Missing closing bar.");
+                    out.extend(&chars[i + 1..i + 1 + len]);
+                    i += len + 2;
+                },
+                '&' if chars.get(i + 1).is_some_and(char::is_ascii_hexdigit) => {
+                    out.push_str("0x");
+                    i += 1;
+                },
+                '{' => {
+                    let upper = chars[i..].iter().take(7).collect::<String>()
+                        .to_ascii_uppercase();
+                    let (value, len) = if upper.starts_with("{PC}") {
+                        (".", 4)
+                    } else if upper.starts_with("{TRUE}") {
+                        ("1", 6)
+                    } else if upper.starts_with("{FALSE}") {
+                        ("0", 7)
+                    } else {
+                        ("{", 1)
+                    };
+                    out.push_str(value);
+                    i += len;
+                },
+                '%' if chars.get(i + 1).is_some_and(|chr| "bBfF"
+                    .contains(*chr)) => {
+                    // Numeric label reference, such as %b1 or %f1
+                    let len = span(i + 2, char::is_ascii_digit);
+                    out.extend(&chars[i + 2..i + 2 + len]);
+                    out.push(chars[i + 1].to_ascii_lowercase());
+                    i += len + 2;
+                },
+                '=' if condition => {
+                    match out.chars().last() {
+                        Some('<' | '>' | '!' | '=') => out.push('='),
+                        _ => out.push_str("=="),
+                    }
+                    i += 1;
+                },
+                '<' if condition && chars.get(i + 1) == Some(&'>') => {
+                    out.push_str("!=");
+                    i += 2;
+                },
+                '/' if condition && chars.get(i + 1) == Some(&'=') => {
+                    out.push_str("!=");
+                    i += 2;
+                },
+                _ if chr.is_ascii_digit() => {
+                    let len = span(i, |chr| chr.is_ascii_alphanumeric()
+                        || *chr == '_');
+                    let number: String = chars[i..i + len].iter().collect();
+                    out.push_str(&Self::number(&number));
+                    i += len;
+                },
+                _ if chr.is_ascii_alphabetic() || chr == '_' => {
+                    let len = span(i, |chr| chr.is_ascii_alphanumeric()
+                        || *chr == '_' || *chr == '.');
+                    let word: String = chars[i..i + len].iter().collect();
+                    let lower = word.to_ascii_lowercase();
+                    match Self::is_reserved(&lower) {
+                        true => out.push_str(&lower),
+                        false => out.push_str(&word),
+                    }
+                    i += len;
+                },
+                _ => {
+                    out.push(chr);
+                    i += 1;
+                },
+            }
+        }
+
+        out
+    }
+
+    /// Translate a number written in base n as `n_digits`, other numbers are
+    /// kept as they are
+    fn number(number: &str) -> String {
+        if let Some((base, digits)) = number.split_once('_') {
+            if let Ok(base @ 2..=9) = base.parse::<u32>() {
+                return u64::from_str_radix(digits, base)
+                    .unwrap_or_else(|_| panic!("Invalid number {}.", number))
+                    .to_string();
+            }
+        }
+        number.to_string()
+    }
+}
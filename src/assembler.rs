@@ -0,0 +1,1612 @@
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::str::FromStr;
+
+use crate::opcode::OpCode;
+use crate::directive::Directive;
+use crate::token::{Token, TokenType};
+use crate::label::Label;
+use crate::options::Options;
+use crate::reloc::{Reloc, RelocTarget, RelocType};
+use crate::section::{Section, SectionFlags, SectionRef, SubSection, Mapping};
+use crate::expr::{Expr, ExprParser, Value};
+use crate::{expect_token, get_token};
+
+use crate::parser::{
+    parser::InstrParser,
+    brxchg::BrXchgParser,
+    branch::BranchParser,
+    mul::MulParser,
+    mullong::MulLongParser,
+    dataproc::DataProcParser,
+    transfer::{PsrTransferParser, DataTransferParser, BlockTransferParser},
+    swap::DataSwapParser,
+    coproc::{CpOpsParser, CpTransfersParser, CpRegTransParser},
+    adr::AdrParser,
+    swi::SwiParser,
+    clz::ClzParser,
+    bkpt::BkptParser,
+    saturate::{SatArithParser, SatParser},
+    rev::RevParser,
+    extend::ExtendParser,
+    pack::PackParser,
+    parallel::ParallelParser,
+    exclusive::ExclusiveParser,
+    system::SystemParser,
+    bitfield::BitfieldParser,
+    movw::MovWideParser,
+};
+
+/// Number of layout passes after which the assembler gives up placing the
+/// labels
+const MAX_LAYOUT_PASSES: usize = 32;
+
+/// Passes made over the statements
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Pass {
+    /// Find the size of each statement and the position of each label
+    Layout,
+    /// Encode the statements, now that every label has an address
+    Emit,
+}
+
+/// A statement of the listing, with the bytes it produced
+pub struct ListingLine<'a> {
+    pub tokens: &'a [Token],
+    pub addr: u32,
+    pub bytes: Vec<u8>,
+    /// True if the bytes are instructions, false if they are data
+    pub code: bool,
+    /// Instruction actually encoded, when it differs from the source
+    pub note: Option<String>,
+}
+
+/// Zero-initialised storage declared by `.comm`
+pub struct Common {
+    pub name: String,
+    pub size: u32,
+    pub align: u32,
+}
+
+/// Block of statements assembled depending on a condition, opened by `.if`
+struct Conditional {
+    /// True if the statements of the current branch are assembled
+    active: bool,
+    /// True if a branch of the block has already been assembled, or if the
+    /// whole block is skipped
+    taken: bool,
+}
+
+/// Literal pool of a subsection, filled by `LDR Rd,=<expression>` and emitted
+/// by `.ltorg` or at the end of the source
+struct Pool {
+    section: SectionRef,
+    /// Distinct values of the pool
+    values: Vec<Value>,
+    /// Number of each load from the pool, along with the index of its value
+    loads: Vec<(usize, usize)>,
+}
+
+/// Position of the value read by a load from a literal pool
+struct Literal {
+    section: SectionRef,
+    /// Offset of the value from the start of its subsection
+    offset: u32,
+    /// Absolute address of the value, known once the layout is done
+    addr: u32,
+}
+
+pub struct Assembler<'a> {
+    /// Tokens of each line
+    statements: Vec<&'a [Token]>,
+    pub sections: Vec<Section>,
+    pub labels: Vec<Label>,
+    pub listing: Vec<ListingLine<'a>>,
+    pass: Pass,
+    /// Subsection where the statements are currently emitted
+    current: SectionRef,
+    /// Subsection that was current before the last section change, restored
+    /// by `.previous`
+    previous: SectionRef,
+    /// Sections saved by `.pushsection`, along with their previous section
+    stack: Vec<(SectionRef, SectionRef)>,
+    /// Number of labels defined so far in the pass
+    n_labels: usize,
+    /// Common symbols left for the linker to allocate
+    pub commons: Vec<Common>,
+    /// Symbols made visible to other files by `.global`
+    pub globals: Vec<String>,
+    /// Symbols defined in other files, declared by `.extern`
+    pub externs: Vec<String>,
+    /// Conditional blocks which are currently open
+    conditionals: Vec<Conditional>,
+    /// True once `.end` is reached
+    ended: bool,
+    /// Symbols defined by `.equ`, `.set` or `symbol = expression`. They are
+    /// kept between passes so that they can be used before their definition.
+    constants: HashMap<String, Value>,
+    /// True if the mnemonics are written in the unified syntax, selected by
+    /// `.syntax`
+    unified: bool,
+    /// Literal pools waiting to be emitted
+    pools: Vec<Pool>,
+    /// Values read by the loads from literal pools, in the order of the loads
+    literals: Vec<Literal>,
+    /// Number of loads from literal pools so far in the pass
+    n_literals: usize,
+    /// Instruction substituted to the current statement, for the listing
+    note: Option<String>,
+    pub options: Options,
+}
+
+impl<'a> Assembler<'a> {
+    pub fn new(tokens: &'a [Token], options: Options) -> Self {
+        // Split the tokens after each endline
+        let statements = tokens
+            .split(|token| token.token_type == TokenType::Endline)
+            .filter(|tokens| !tokens.is_empty())
+            .collect();
+
+        let text = SectionRef { section: 0, subsection: 0 };
+
+        Self {
+            statements,
+            sections: vec![Section::new(".text")],
+            labels: Vec::new(),
+            listing: Vec::new(),
+            pass: Pass::Layout,
+            current: text,
+            previous: text,
+            stack: Vec::new(),
+            n_labels: 0,
+            commons: Vec::new(),
+            globals: Vec::new(),
+            externs: Vec::new(),
+            conditionals: Vec::new(),
+            ended: false,
+            constants: HashMap::new(),
+            unified: options.unified,
+            pools: Vec::new(),
+            literals: Vec::new(),
+            n_literals: 0,
+            note: None,
+            options,
+        }
+    }
+
+    /// Assemble the statements into the sections
+    pub fn assemble(&mut self) {
+        // The size of some statements depends on the address of the labels
+        // they refer to, so the labels are placed again until their address
+        // stops changing
+        for _ in 0..MAX_LAYOUT_PASSES {
+            let addrs: Vec<u32> = self.labels.iter()
+                .map(|label| label.addr)
+                .chain(self.literals.iter().map(|literal| literal.addr))
+                .collect();
+
+            self.run(Pass::Layout);
+            self.layout();
+
+            if self.labels.iter().map(|label| label.addr)
+                .chain(self.literals.iter().map(|literal| literal.addr))
+                .eq(addrs) {
+                // Last pass, encoding the statements
+                self.run(Pass::Emit);
+                return;
+            }
+        }
+
+        panic!("Label addresses did not settle after {} passes.",
+            MAX_LAYOUT_PASSES);
+    }
+
+    /// Return the flat image made of every section placed at its address
+    pub fn image(&self) -> Vec<u8> {
+        let mut image = Vec::new();
+        for section in &self.sections {
+            image.resize(section.addr as usize, 0);
+            image.extend(section.data());
+        }
+        image
+    }
+
+    fn run(&mut self, pass: Pass) {
+        self.pass = pass;
+
+        // Start back from the beginning of `.text`
+        for section in &mut self.sections {
+            section.clear();
+        }
+        self.current = SectionRef { section: 0, subsection: 0 };
+        self.previous = self.current;
+        self.stack.clear();
+        self.sections[0].subsection(0);
+
+        self.n_labels = 0;
+        self.n_literals = 0;
+        self.pools.clear();
+        self.conditionals.clear();
+        self.ended = false;
+        self.unified = self.options.unified;
+        // Common and external symbols are collected during the layout, so
+        // that they are all known by the last pass
+        if pass == Pass::Layout {
+            self.commons.clear();
+            self.globals.clear();
+            self.externs.clear();
+        }
+
+        for i in 0..self.statements.len() {
+            let tokens = self.statements[i];
+
+            // Labels may precede a statement on the same line
+            let n = tokens.iter()
+                .take_while(|token| token.token_type == TokenType::Label)
+                .count();
+            let statement = &tokens[n..];
+
+            // In the branches of conditional blocks which are not taken, only
+            // the directives delimiting the blocks are followed and listed
+            let assembling = self.assembling();
+            if !assembling && !Self::is_conditional(statement) {
+                continue;
+            }
+
+            // Instructions are aligned before their labels are defined, the
+            // padding being left out of their listing
+            if let Some(align) = Self::code_alignment(statement)
+                .filter(|_| assembling) {
+                self.align_code(align);
+            }
+
+            let current = self.current;
+            let addr = self.addr();
+            let size = self.subsection().data.len();
+
+            // Labels in a skipped branch are not defined
+            let labels = if assembling { &tokens[..n] } else { &[] };
+            for label in labels {
+                // Remove the trailing ':' char
+                let name = &label.value[..label.value.len() - 1];
+                let offset = self.subsection().data.len() as u32;
+                self.define_label(name, label.line, self.current, offset);
+            }
+
+            match statement.first().map(|token| token.token_type) {
+                None => continue,
+                Some(TokenType::Directive) => self.directive(statement),
+                // symbol = expression
+                Some(TokenType::Keyword) if statement.get(1)
+                    .is_some_and(|token| token.token_type == TokenType::Equal)
+                    => self.define_constant(&statement[0].value, statement, 2),
+                Some(_) => self.instruction(statement),
+            }
+
+            if pass == Pass::Emit {
+                // Section directives change the current subsection without
+                // emitting anything
+                let bytes = match self.current == current {
+                    true => self.subsection().data[size..].to_vec(),
+                    false => Vec::new(),
+                };
+                let code = self.subsection().mapping()
+                    .is_some_and(|mapping| mapping != Mapping::Data);
+                let note = self.note.take();
+                self.listing.push(ListingLine { tokens, addr, bytes, code,
+                    note });
+            }
+
+            if self.ended {
+                break;
+            }
+        }
+
+        if !self.conditionals.is_empty() {
+            panic!(".if without matching .endif.");
+        }
+
+        // The literal pools left are emitted at the end of their subsection
+        while let Some(pool) = self.pools.first() {
+            self.current = pool.section;
+            self.pool();
+        }
+
+        // Forget the labels of the previous pass which were not defined again
+        if pass == Pass::Layout {
+            self.labels.truncate(self.n_labels);
+            self.literals.truncate(self.n_literals);
+        }
+    }
+
+    /// Return true if the current statements are assembled, which is when
+    /// every open conditional block is in the branch taken
+    fn assembling(&self) -> bool {
+        self.conditionals.iter().all(|conditional| conditional.active)
+    }
+
+    /// Return true if `tokens` is one of the directives delimiting a
+    /// conditional block
+    fn is_conditional(tokens: &[Token]) -> bool {
+        tokens.first()
+            .and_then(|token| Directive::from_str(&token.value).ok())
+            .is_some_and(|directive| matches!(directive, Directive::If
+                | Directive::ElseIf | Directive::Else | Directive::EndIf))
+    }
+
+    /// Return the alignment in bytes of `tokens` if it is an instruction,
+    /// which is a halfword for the Thumb instructions of `.inst.n` and
+    /// `.inst.w`
+    fn code_alignment(tokens: &[Token]) -> Option<u32> {
+        let token = tokens.first()?;
+        match token.token_type {
+            // Anything but symbol = expression
+            TokenType::Keyword => match tokens.get(1) {
+                Some(token) if token.token_type == TokenType::Equal => None,
+                _ => Some(4),
+            },
+            TokenType::Directive => match Directive::from_str(&token.value) {
+                Ok(Directive::Inst) => Some(4),
+                Ok(Directive::InstN | Directive::InstW) => Some(2),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Evaluate the condition of `.if` or `.elseif`
+    fn condition(&self, tokens: &[Token]) -> bool {
+        match Self::parse_exprs(tokens, 1).as_slice() {
+            [expr] => self.eval_known(expr) != 0,
+            _ => panic!("Expected a single expression."),
+        }
+    }
+
+    /// Parse a list of symbols separated by commas: symbol{,symbol}
+    fn parse_symbols(tokens: &[Token]) -> Vec<String> {
+        let mut symbols = Vec::new();
+
+        let mut idx = 1;
+        while idx < tokens.len() {
+            if idx > 1 {
+                expect_token!(tokens, idx, TokenType::Comma);
+                idx += 1;
+            }
+
+            let token = get_token!(tokens, idx, [TokenType::Keyword]);
+            symbols.push(token.value.clone());
+            idx += 1;
+        }
+
+        symbols
+    }
+
+    /// Place each section after the previous one and give the labels their
+    /// address
+    fn layout(&mut self) {
+        let mut addr = 0u32;
+        for section in &mut self.sections {
+            let size = section.layout();
+            addr = addr.next_multiple_of(section.align());
+            section.addr = addr;
+            addr += size;
+        }
+
+        for label in &mut self.labels {
+            let section = &self.sections[label.section.section];
+            label.addr = section.addr
+                + section.subsections[&label.section.subsection].offset
+                + label.offset;
+        }
+
+        for literal in &mut self.literals {
+            let section = &self.sections[literal.section.section];
+            literal.addr = section.addr
+                + section.subsections[&literal.section.subsection].offset
+                + literal.offset;
+        }
+    }
+
+    /// Define the label `name` at `offset` in the subsection `section`
+    fn define_label(&mut self, name: &str, line: usize, section: SectionRef,
+        offset: u32) {
+        // Labels are placed during the layout, each pass defining them in the
+        // same order
+        if self.pass == Pass::Layout {
+            // Move the label if it was placed by a previous pass, keeping its
+            // address until the layout is done
+            match self.labels.get_mut(self.n_labels) {
+                Some(placed) if placed.name == name && placed.line == line => {
+                    placed.section = section;
+                    placed.offset = offset;
+                },
+                // The labels defined from here differ from the previous pass,
+                // when a conditional block changed branch
+                _ => {
+                    self.labels.truncate(self.n_labels);
+                    let mut label = Label::new(name, line, section, offset);
+                    if Label::is_cheap_local(name) {
+                        label.scope = Label::scope(&self.labels, line)
+                            .map(|scope| scope.name.clone());
+                    }
+                    self.labels.push(label);
+                },
+            }
+        }
+        self.n_labels += 1;
+    }
+
+    /// Define the constant `name` as the expression starting at index `idx`
+    fn define_constant(&mut self, name: &str, tokens: &[Token], idx: usize) {
+        let value = match Self::parse_exprs(tokens, idx).as_slice() {
+            [expr] => self.eval(expr),
+            _ => panic!("Expected a single expression."),
+        };
+        self.constants.insert(name.to_string(), value);
+    }
+
+    /// Return the current subsection
+    fn subsection(&mut self) -> &mut SubSection {
+        self.sections[self.current.section].subsection(self.current.subsection)
+    }
+
+    /// Return the offset of the location counter from the start of its
+    /// section, only meaningful once the layout is done
+    fn offset(&self) -> u32 {
+        let sub = &self.sections[self.current.section].subsections
+            [&self.current.subsection];
+        sub.offset + sub.data.len() as u32
+    }
+
+    /// Return the address of the location counter, only meaningful once the
+    /// layout is done
+    fn addr(&self) -> u32 {
+        self.sections[self.current.section].addr + self.offset()
+    }
+
+    /// Return the value of the address `offset` in the section `section`.
+    ///
+    /// When producing an object file, an address is only known relative to
+    /// its section, so the value comes with the section that the linker must
+    /// add to it.
+    fn address(&self, section: usize, offset: i64) -> Value {
+        match self.options.object {
+            true => Value { value: offset,
+                target: Some(RelocTarget::Section(section)) },
+            false => Value::new(self.sections[section].addr as i64 + offset),
+        }
+    }
+
+    /// Return the value of the symbol `name` used on line `line`
+    fn symbol(&self, name: &str, line: usize) -> Option<Value> {
+        if let Some(label) = Label::find(&self.labels, name, line) {
+            let section = label.section.section;
+            let offset = label.addr as i64 - self.sections[section].addr as i64;
+            return Some(self.address(section, offset));
+        }
+
+        if let Some(value) = self.constants.get(name) {
+            return Some(value.clone());
+        }
+
+        // Common symbols are allocated by the linker, and external symbols
+        // are defined in other files
+        if self.commons.iter().any(|common| common.name == name)
+            || (self.options.object && self.externs.iter()
+                .chain(&self.globals).any(|symbol| symbol == name)) {
+            return Some(Value { value: 0,
+                target: Some(RelocTarget::Symbol(name.to_string())) });
+        }
+
+        None
+    }
+
+    fn try_eval(&self, expr: &Expr) -> Result<Value, String> {
+        match expr {
+            Expr::Number(value) => Ok(Value::new(*value)),
+            Expr::Location => Ok(self.address(self.current.section,
+                self.offset() as i64)),
+            Expr::Symbol(name, line) => self.symbol(name, *line)
+                .ok_or(format!("Undefined symbol {}.", name)),
+            Expr::Unary(op, expr) => self.try_eval(expr)?.unary(*op),
+            Expr::Binary(op, lhs, rhs) => {
+                self.try_eval(lhs)?.binary(*op, self.try_eval(rhs)?)
+            },
+        }
+    }
+
+    /// Evaluate `expr` at the location counter
+    fn eval(&self, expr: &Expr) -> Value {
+        match self.try_eval(expr) {
+            Ok(value) => value,
+            // During the layout, labels which are not placed yet make the
+            // expression null
+            Err(_) if self.pass == Pass::Layout => Value::new(0),
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// Evaluate `expr`, which must be known when assembling
+    fn eval_known(&self, expr: &Expr) -> i64 {
+        let value = self.eval(expr);
+        if value.target.is_some() {
+            panic!("Expected a value known when assembling.");
+        }
+        value.value
+    }
+
+    /// Evaluate `expr`, which must be a 32-bit unsigned value known when
+    /// assembling
+    fn eval_unsigned(&self, expr: &Expr) -> u32 {
+        let value = self.eval_known(expr);
+        u32::try_from(value)
+            .unwrap_or_else(|_| panic!("Invalid unsigned value {}.", value))
+    }
+
+    /// Evaluate `expr` as an address in the flat image. Instructions are
+    /// encoded with the final address of what they refer to.
+    fn eval_absolute(&self, expr: &Expr) -> i64 {
+        let value = self.eval(expr);
+        match value.target {
+            None => value.value,
+            Some(RelocTarget::Section(section)) => {
+                self.sections[section].addr as i64 + value.value
+            },
+            Some(RelocTarget::Symbol(name)) => {
+                panic!("The address of {} is only known when linking.", name)
+            },
+        }
+    }
+
+    /// Return true if the value of `expr` does not depend on the address of a
+    /// label
+    fn is_constant(&self, expr: &Expr) -> bool {
+        match expr {
+            Expr::Number(_) => true,
+            Expr::Location => false,
+            Expr::Symbol(name, line) => {
+                Label::find(&self.labels, name, *line).is_none()
+                    && self.constants.get(name)
+                        .is_some_and(|value| value.target.is_none())
+            },
+            Expr::Unary(_, expr) => self.is_constant(expr),
+            Expr::Binary(_, lhs, rhs) => {
+                self.is_constant(lhs) && self.is_constant(rhs)
+            },
+        }
+    }
+
+    /// Return the symbol of another file that the expression at index `idx`
+    /// refers to, along with the number added to its address. In object
+    /// files, a label of another section is only placed by the linker too,
+    /// and is referred to from the start of its section.
+    fn external(&self, tokens: &[Token], idx: usize)
+        -> Option<(RelocTarget, i64)> {
+        let (expr, _) = ExprParser::parse(tokens, idx)?;
+        match self.try_eval(&expr) {
+            Ok(Value { value, target: Some(RelocTarget::Symbol(name)) }) => {
+                Some((RelocTarget::Symbol(name), value))
+            },
+            Ok(Value { value, target: Some(RelocTarget::Section(section)) })
+                if section != self.current.section => {
+                Some((RelocTarget::Section(section), value))
+            },
+            _ => None,
+        }
+    }
+
+    /// Replace the expressions found in the operands of an instruction by the
+    /// number they evaluate to, so that the instruction parsers only deal with
+    /// numbers. A label on its own is kept for the parsers, which encode it
+    /// relative to the instruction.
+    fn resolve(&self, tokens: &[Token]) -> Vec<Token> {
+        let mut resolved = vec![tokens[0].clone()];
+
+        let mut idx = 1;
+        while idx < tokens.len() {
+            let token = &tokens[idx];
+            // Expressions start with '#' or at the beginning of an operand
+            let start = token.token_type == TokenType::Hash
+                || idx == 1
+                || matches!(tokens[idx - 1].token_type, TokenType::Comma
+                    | TokenType::OpenBracket);
+
+            match ExprParser::parse(tokens, idx) {
+                Some((expr, next)) if start => {
+                    // Addresses placed by the linker cannot be encoded in
+                    // the instruction, which refers to its own section only
+                    if self.external(tokens, idx).is_some() {
+                        let name = match token.token_type {
+                            TokenType::Hash => &tokens[idx + 1].value,
+                            _ => &token.value,
+                        };
+                        panic!("The address of {} is only known when linking.",
+                            name);
+                    }
+
+                    let single = match &expr {
+                        Expr::Number(_) => true,
+                        Expr::Symbol(name, _) => {
+                            !self.constants.contains_key(name)
+                        },
+                        _ => false,
+                    };
+
+                    if single && next == idx + 1 {
+                        resolved.push(token.clone());
+                    } else {
+                        let hash = match token.token_type {
+                            TokenType::Hash => "#",
+                            _ if token.value.starts_with('#') => "#",
+                            _ => "",
+                        };
+                        resolved.push(Token {
+                            token_type: TokenType::Number,
+                            value: format!("{}{}", hash,
+                                self.eval_absolute(&expr)),
+                            line: token.line,
+                        });
+                    }
+                    idx = next;
+                    continue;
+                },
+                None if token.token_type == TokenType::Hash => {
+                    panic!("Invalid expression!")
+                },
+                _ => resolved.push(token.clone()),
+            }
+            idx += 1;
+        }
+
+        resolved
+    }
+
+    fn emit(&mut self, bytes: &[u8], mapping: Mapping) {
+        self.subsection().emit(bytes, mapping);
+    }
+
+    /// Pad the current subsection with zeros up to a multiple of `align`
+    /// bytes
+    fn align(&mut self, align: u32) {
+        if !align.is_power_of_two() {
+            panic!("Alignment must be a power of 2.");
+        }
+
+        let sub = self.subsection();
+        sub.align = sub.align.max(align);
+        let size = sub.data.len().next_multiple_of(align as usize);
+        sub.data.resize(size, 0);
+    }
+
+    /// Pad the current subsection with zeros up to a multiple of `align`
+    /// bytes before an instruction, moving along the labels placed right
+    /// before it on the previous lines
+    fn align_code(&mut self, align: u32) {
+        let offset = self.subsection().data.len() as u32;
+        self.align(align);
+        let aligned = self.subsection().data.len() as u32;
+
+        if self.pass == Pass::Layout && aligned != offset {
+            let current = self.current;
+            for label in self.labels[..self.n_labels].iter_mut().rev()
+                .take_while(|label| label.section == current
+                    && label.offset == offset) {
+                label.offset = aligned;
+            }
+        }
+    }
+
+    /// Return the index of the section `name`, creating it if needed
+    fn section(&mut self, name: &str) -> usize {
+        match self.sections.iter().position(|section| section.name == name) {
+            Some(idx) => idx,
+            None => {
+                self.sections.push(Section::new(name));
+                self.sections.len() - 1
+            },
+        }
+    }
+
+    /// Make the subsection `subsection` of the section `name` the current
+    /// one, giving the section `flags` if any
+    fn switch_section(&mut self, name: &str, subsection: u32,
+        flags: Option<SectionFlags>) {
+        let section = self.section(name);
+        if flags.is_some() {
+            self.sections[section].flags = flags;
+        }
+
+        self.previous = self.current;
+        self.current = SectionRef { section, subsection };
+        self.subsection();
+    }
+
+    /// Parse a list of expressions separated by commas, starting at index
+    /// `idx` and going up to the end of the statement
+    fn parse_exprs(tokens: &[Token], idx: usize) -> Vec<Expr> {
+        let mut exprs = Vec::new();
+
+        let mut idx = idx;
+        while idx < tokens.len() {
+            if !exprs.is_empty() {
+                expect_token!(tokens, idx, TokenType::Comma);
+                idx += 1;
+            }
+
+            let (expr, next) = ExprParser::parse(tokens, idx)
+                .expect("Invalid expression!");
+            exprs.push(expr);
+            idx = next;
+        }
+
+        exprs
+    }
+
+    /// Parse the optional subsection number at index `idx`
+    fn parse_subsection(&self, tokens: &[Token], idx: usize) -> u32 {
+        match Self::parse_exprs(tokens, idx).as_slice() {
+            [] => 0,
+            [expr] => self.eval_unsigned(expr),
+            _ => panic!("Invalid subsection number!"),
+        }
+    }
+
+    /// Parse a section name followed either by its optional subsection
+    /// number or by its flags and type:
+    /// name{,subsection} or name,"flags"{,%<progbits|nobits>}
+    fn parse_section<'t>(&self, tokens: &'t [Token])
+        -> (&'t str, u32, Option<SectionFlags>) {
+        let name = match tokens.get(1) {
+            Some(token) if token.token_type == TokenType::Keyword
+                || token.token_type == TokenType::Directive => &token.value,
+            _ => panic!("Expected a section name."),
+        };
+
+        if tokens.get(2).is_none() {
+            return (name, 0, None);
+        }
+        expect_token!(tokens, 2, TokenType::Comma);
+        match tokens.get(3) {
+            Some(token) if token.token_type == TokenType::String => {},
+            _ => return (name, self.parse_subsection(tokens, 3), None),
+        }
+
+        let mut flags = SectionFlags::default();
+        for chr in tokens[3].value.trim_matches('"').chars() {
+            match chr {
+                'a' => flags.alloc = true,
+                'w' => flags.write = true,
+                'x' => flags.exec = true,
+                _ => panic!("Invalid section flag '{}'.", chr),
+            }
+        }
+
+        if tokens.get(4).is_some() {
+            expect_token!(tokens, 4, TokenType::Comma);
+            expect_token!(tokens, 5, TokenType::Percent);
+            flags.nobits = match tokens.get(6).map(|t| t.value.as_str()) {
+                Some("progbits") => false,
+                Some("nobits") => true,
+                _ => panic!("Expected a section type: %progbits or %nobits."),
+            };
+            if tokens.get(7).is_some() {
+                panic!("Unexpected tokens after the section type.");
+            }
+        }
+
+        (name, 0, Some(flags))
+    }
+
+    /// Check that `value` fits in `size` bytes, either as a signed or as an
+    /// unsigned value, and return its bytes in little endian
+    fn value_bytes(value: i64, size: usize) -> Vec<u8> {
+        let bits = size as u32 * 8;
+        if value < -(1 << (bits - 1)) || value >= 1 << bits {
+            panic!("Value {} does not fit in {} bits.", value, bits);
+        }
+        value.to_le_bytes()[..size].to_vec()
+    }
+
+    /// Emit `value` on `size` bytes, along with a relocation if the linker
+    /// has to add the address of `target`
+    fn emit_value(&mut self, value: Value, size: usize, mapping: Mapping) {
+        if let Some(target) = value.target {
+            if size != 4 {
+                panic!("Only 32-bit values can be relocated.");
+            }
+
+            let sub = self.subsection();
+            sub.relocs.push(Reloc {
+                offset: sub.data.len() as u32,
+                target,
+                reloc_type: RelocType::Abs32,
+            });
+        }
+
+        self.emit(&Self::value_bytes(value.value, size), mapping);
+    }
+
+    /// Emit the literal pool of the current subsection, if any
+    fn pool(&mut self) {
+        let pool = match self.pools.iter()
+            .position(|pool| pool.section == self.current) {
+            Some(idx) => self.pools.remove(idx),
+            None => return,
+        };
+
+        self.align(4);
+        let mut offsets = Vec::new();
+        for value in pool.values {
+            offsets.push(self.subsection().data.len() as u32);
+            self.emit_value(value, 4, Mapping::Data);
+        }
+
+        // The loads find their value where the layout placed it
+        if self.pass == Pass::Layout {
+            for (load, idx) in pool.loads {
+                while self.literals.len() <= load {
+                    self.literals.push(Literal { section: self.current,
+                        offset: 0, addr: 0 });
+                }
+                self.literals[load].section = self.current;
+                self.literals[load].offset = offsets[idx];
+            }
+        }
+    }
+
+    /// Allocate `size` bytes aligned on `align` bytes at the end of `.bss`
+    /// and define the label `name` there
+    fn allocate_bss(&mut self, name: &str, line: usize, size: u32,
+        align: u32) {
+        let section = SectionRef { section: self.section(".bss"),
+            subsection: 0 };
+
+        let sub = self.sections[section.section].subsection(0);
+        sub.align = sub.align.max(align);
+        let offset = sub.data.len().next_multiple_of(align as usize);
+        sub.data.resize(offset + size as usize, 0);
+
+        self.define_label(name, line, section, offset as u32);
+    }
+
+    /// Encode `value` in unsigned LEB128: 7 bits per byte, the lowest first,
+    /// with the high bit set on every byte but the last
+    fn uleb128(mut value: u64) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+            if value == 0 {
+                bytes.push(byte);
+                return bytes;
+            }
+            bytes.push(byte | 0x80);
+        }
+    }
+
+    /// Encode `value` in signed LEB128, stopping once the remaining bits are
+    /// all copies of the sign bit of the last byte
+    fn sleb128(mut value: i64) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+            let sign = byte & 0x40 != 0;
+            if (value == 0 && !sign) || (value == -1 && sign) {
+                bytes.push(byte);
+                return bytes;
+            }
+            bytes.push(byte | 0x80);
+        }
+    }
+
+    fn directive(&mut self, tokens: &[Token]) {
+        let directive = Directive::from_str(&tokens[0].value)
+            .expect("Invalid directive!");
+
+        match directive {
+            Directive::Section => {
+                // .section name{,subsection}
+                // .section name,"flags"{,%type}
+                let (name, subsection, flags) = self.parse_section(tokens);
+                self.switch_section(name, subsection, flags);
+            },
+            Directive::Text | Directive::Data | Directive::Bss => {
+                // <.text|.data|.bss> {subsection}
+                let subsection = self.parse_subsection(tokens, 1);
+                self.switch_section(&tokens[0].value, subsection, None);
+            },
+            Directive::PushSection => {
+                // .pushsection name{,subsection}
+                // .pushsection name,"flags"{,%type}
+                self.stack.push((self.current, self.previous));
+                let (name, subsection, flags) = self.parse_section(tokens);
+                self.switch_section(name, subsection, flags);
+            },
+            Directive::PopSection => {
+                // .popsection
+                let (current, previous) = self.stack.pop()
+                    .expect(".popsection without matching .pushsection.");
+                self.current = current;
+                self.previous = previous;
+            },
+            Directive::Previous => {
+                // .previous
+                std::mem::swap(&mut self.current, &mut self.previous);
+            },
+            Directive::SubSection => {
+                // .subsection subsection
+                let section = self.sections[self.current.section].name.clone();
+                let subsection = self.parse_subsection(tokens, 1);
+                self.switch_section(&section, subsection, None);
+            },
+            Directive::Align | Directive::BAlign => {
+                // .align <power of 2>
+                // .balign <number of bytes>
+                let value = match Self::parse_exprs(tokens, 1).as_slice() {
+                    [expr] => self.eval_unsigned(expr),
+                    // Align on a word by default
+                    [] if directive == Directive::Align => 2,
+                    [] => 4,
+                    _ => panic!("Invalid alignment!"),
+                };
+
+                match directive {
+                    Directive::Align if value >= 32 => {
+                        panic!("Alignment must be a power of 2.")
+                    },
+                    Directive::Align => self.align(1 << value),
+                    _ => self.align(value),
+                }
+            },
+            Directive::Byte | Directive::HWord | Directive::Word => {
+                // <.byte|.hword|.word> <expression>{,<expression>}
+                let size = match directive {
+                    Directive::Byte => 1,
+                    Directive::HWord => 2,
+                    _ => 4,
+                };
+
+                // Each value is evaluated where it is emitted
+                for expr in Self::parse_exprs(tokens, 1) {
+                    let value = self.eval(&expr);
+                    self.emit_value(value, size, Mapping::Data);
+                }
+            },
+            Directive::ULeb128 | Directive::SLeb128 => {
+                // <.uleb128|.sleb128> <expression>{,<expression>}
+                for expr in Self::parse_exprs(tokens, 1) {
+                    let value = self.eval_known(&expr);
+                    let bytes = match directive {
+                        Directive::ULeb128 if value < 0 => {
+                            panic!("Negative value {} in .uleb128.", value)
+                        },
+                        Directive::ULeb128 => Self::uleb128(value as u64),
+                        _ => Self::sleb128(value),
+                    };
+                    self.emit(&bytes, Mapping::Data);
+                }
+            },
+            Directive::Space => {
+                // <.space|.skip> <size>{,<fill>}
+                let exprs = Self::parse_exprs(tokens, 1);
+                let (size, fill) = match exprs.as_slice() {
+                    [size] => (self.eval_unsigned(size), 0),
+                    [size, fill] => {
+                        (self.eval_unsigned(size), self.eval_known(fill))
+                    },
+                    _ => panic!("Expected a size and an optional fill value."),
+                };
+
+                let fill = Self::value_bytes(fill, 1)[0];
+                self.emit(&vec![fill; size as usize], Mapping::Data);
+            },
+            Directive::Comm | Directive::LComm => {
+                // <.comm|.lcomm> symbol,<size>{,<align>}
+                let name = &get_token!(tokens, 1, [TokenType::Keyword]).value;
+                expect_token!(tokens, 2, TokenType::Comma);
+
+                let exprs = Self::parse_exprs(tokens, 3);
+                let (size, align) = match exprs.as_slice() {
+                    [size] => {
+                        // Align on the largest power of 2 not above the size,
+                        // up to a doubleword
+                        let size = self.eval_unsigned(size);
+                        (size, 1 << size.max(1).ilog2().min(3))
+                    },
+                    [size, align] => {
+                        (self.eval_unsigned(size), self.eval_unsigned(align))
+                    },
+                    _ => panic!("Expected a size and an optional alignment."),
+                };
+                if !align.is_power_of_two() {
+                    panic!("Alignment must be a power of 2.");
+                }
+
+                if directive == Directive::Comm && self.options.object {
+                    // Left for the linker to allocate
+                    if self.pass == Pass::Layout {
+                        self.commons.push(Common { name: name.clone(), size,
+                            align });
+                    }
+                } else {
+                    self.allocate_bss(name, tokens[0].line, size, align);
+                }
+            },
+            Directive::Inst => {
+                // .inst <expression>{,<expression>}
+                for expr in Self::parse_exprs(tokens, 1) {
+                    let value = self.eval(&expr);
+                    self.emit_value(value, 4, Mapping::Arm);
+                }
+            },
+            Directive::InstN | Directive::InstW => {
+                // <.inst.n|.inst.w> <expression>{,<expression>}
+                for expr in Self::parse_exprs(tokens, 1) {
+                    let value = self.eval_known(&expr);
+                    if directive == Directive::InstN {
+                        self.emit(&Self::value_bytes(value, 2), Mapping::Thumb);
+                    } else {
+                        // 32-bit Thumb instructions are made of two
+                        // halfwords, the most significant one first
+                        let bytes = Self::value_bytes(value, 4);
+                        self.emit(&bytes[2..], Mapping::Thumb);
+                        self.emit(&bytes[..2], Mapping::Thumb);
+                    }
+                }
+            },
+            Directive::Equ => {
+                // <.equ|.set> symbol,<expression>
+                let name = &get_token!(tokens, 1, [TokenType::Keyword]).value;
+                expect_token!(tokens, 2, TokenType::Comma);
+                self.define_constant(name, tokens, 3);
+            },
+            Directive::Ltorg => {
+                // <.ltorg|.pool>
+                self.pool();
+            },
+            Directive::Syntax => {
+                // .syntax <unified|divided>
+                let syntax = get_token!(tokens, 1, [TokenType::Keyword]);
+                self.unified = match syntax.value.to_lowercase().as_str() {
+                    "unified" => true,
+                    "divided" => false,
+                    _ => panic!("Unknown syntax {}.", syntax.value),
+                };
+            },
+            Directive::Global | Directive::Extern => {
+                // <.global|.extern> symbol{,symbol}
+                if self.pass == Pass::Layout {
+                    let symbols = match directive {
+                        Directive::Global => &mut self.globals,
+                        _ => &mut self.externs,
+                    };
+                    for symbol in Self::parse_symbols(tokens) {
+                        if !symbols.contains(&symbol) {
+                            symbols.push(symbol);
+                        }
+                    }
+                }
+            },
+            Directive::If => {
+                // .if <expression>
+                let parent = self.assembling();
+                let active = parent && self.condition(tokens);
+                self.conditionals.push(Conditional { active,
+                    taken: active || !parent });
+            },
+            Directive::ElseIf => {
+                // .elseif <expression>
+                let conditional = self.conditionals.pop()
+                    .expect(".elseif without matching .if.");
+                let active = !conditional.taken && self.condition(tokens);
+                self.conditionals.push(Conditional { active,
+                    taken: conditional.taken || active });
+            },
+            Directive::Else => {
+                // .else
+                let conditional = self.conditionals.last_mut()
+                    .expect(".else without matching .if.");
+                conditional.active = !conditional.taken;
+                conditional.taken = true;
+            },
+            Directive::EndIf => {
+                // .endif
+                self.conditionals.pop().expect(".endif without matching .if.");
+            },
+            Directive::End => {
+                // .end
+                self.ended = true;
+            },
+        }
+    }
+
+    /// Return the statement made of `tokens` as text
+    fn format(tokens: &[Token]) -> String {
+        let mut text = tokens[0].value.clone();
+        for token in &tokens[1..] {
+            if token.token_type != TokenType::Comma {
+                text.push(' ');
+            }
+            text.push_str(&token.value);
+        }
+        text
+    }
+
+    /// Decode the mnemonic of an instruction, and return its opcode along
+    /// with the tokens of the instruction written in the divided syntax,
+    /// which the parsers expect. Pseudo-instructions, such as shifts written
+    /// as instructions, are turned into the instructions they stand for.
+    fn mnemonic(&self, tokens: &[Token]) -> (OpCode, Vec<Token>) {
+        let (opcode, cond, suffix) = InstrParser::parse_mnemonic(&tokens[0],
+            self.unified)
+            .unwrap_or_else(|| panic!("Invalid opcode {}!", tokens[0].value));
+        let arch = opcode.arch(&suffix);
+        if arch > self.options.arch {
+            panic!("{} requires {}, the architecture is {}.", tokens[0].value,
+                arch, self.options.arch);
+        }
+        let cond = cond.map(|cond| cond.to_string().to_lowercase())
+            .unwrap_or_default();
+
+        let token = |token_type: TokenType, value: &str| Token {
+            token_type,
+            value: value.to_string(),
+            line: tokens[0].line,
+        };
+        let keyword = |value: &str| token(TokenType::Keyword, value);
+        let comma = || token(TokenType::Comma, ",");
+        // Mnemonic of the instruction standing for the pseudo-instruction
+        let mnemonic = |opcode: &str, suffix: &str| {
+            keyword(&format!("{}{}{}", opcode, cond, suffix))
+        };
+
+        match opcode {
+            OpCode::LSL | OpCode::LSR | OpCode::ASR | OpCode::ROR => {
+                // <shift>{S}{cond} Rd,Rm,<Rs|#expression> is
+                // MOV{cond}{S} Rd,Rm,<shift> <Rs|#expression>, and Rm is Rd
+                // if omitted
+                expect_token!(tokens, 2, TokenType::Comma);
+                let operand = match tokens.get(4) {
+                    Some(token) if token.token_type == TokenType::Comma => 5,
+                    _ => 3,
+                };
+
+                let mut moved = vec![mnemonic("mov", &suffix)];
+                moved.extend_from_slice(&tokens[1..3]);
+                moved.extend_from_slice(&tokens[operand - 2..operand]);
+                moved.push(keyword(&opcode.to_string().to_lowercase()));
+                moved.extend_from_slice(&tokens[operand..]);
+                (OpCode::MOV, moved)
+            },
+            OpCode::RRX => {
+                // RRX{S}{cond} Rd,Rm is MOV{cond}{S} Rd,Rm,RRX
+                let mut moved = vec![mnemonic("mov", &suffix)];
+                moved.extend_from_slice(&tokens[1..]);
+                moved.extend([comma(), keyword("rrx")]);
+                (OpCode::MOV, moved)
+            },
+            OpCode::NOP => {
+                // NOP{cond} is MOV{cond} R0,R0
+                (OpCode::MOV, vec![mnemonic("mov", ""), keyword("r0"), comma(),
+                    keyword("r0")])
+            },
+            OpCode::NEG => {
+                // NEG{S}{cond} Rd,Rm is RSB{cond}{S} Rd,Rm,#0
+                let mut negated = vec![mnemonic("rsb", &suffix)];
+                negated.extend_from_slice(&tokens[1..]);
+                negated.extend([comma(), token(TokenType::Number, "#0")]);
+                (OpCode::RSB, negated)
+            },
+            OpCode::CPY => {
+                // CPY{cond} Rd,Rm is MOV{cond} Rd,Rm
+                let mut moved = vec![mnemonic("mov", "")];
+                moved.extend_from_slice(&tokens[1..]);
+                (OpCode::MOV, moved)
+            },
+            OpCode::PUSH | OpCode::POP => {
+                let push = opcode == OpCode::PUSH;
+                let sp = keyword("sp");
+
+                // A single register is stored with STR{cond} Rd,[SP,#-4]! and
+                // loaded with LDR{cond} Rd,[SP],#4
+                if tokens.len() == 4
+                    && tokens[1].token_type == TokenType::OpenCurlyBrace
+                    && tokens[3].token_type == TokenType::CloseCurlyBrace {
+                    let open = token(TokenType::OpenBracket, "[");
+                    let close = token(TokenType::CloseBracket, "]");
+                    let rd = tokens[2].clone();
+                    return match push {
+                        true => (OpCode::STR, vec![mnemonic("str", ""), rd,
+                            comma(), open, sp, comma(),
+                            token(TokenType::Number, "#-4"), close,
+                            token(TokenType::Exclamation, "!")]),
+                        false => (OpCode::LDR, vec![mnemonic("ldr", ""), rd,
+                            comma(), open, sp, close, comma(),
+                            token(TokenType::Number, "#4")]),
+                    };
+                }
+
+                // Otherwise PUSH{cond} <Rlist> is STM{cond}DB SP!,<Rlist> and
+                // POP{cond} <Rlist> is LDM{cond}IA SP!,<Rlist>
+                let (opcode, transfer) = match push {
+                    true => (OpCode::STM, mnemonic("stm", "db")),
+                    false => (OpCode::LDM, mnemonic("ldm", "ia")),
+                };
+                let mut transfer = vec![transfer, sp,
+                    token(TokenType::Exclamation, "!"), comma()];
+                transfer.extend_from_slice(&tokens[1..]);
+                (opcode, transfer)
+            },
+            _ => {
+                let mut tokens = tokens.to_vec();
+                tokens[0] = mnemonic(&opcode.to_string().to_lowercase(),
+                    &suffix);
+                (opcode, tokens)
+            },
+        }
+    }
+
+    /// Turn `LDR{cond} Rd,=<expression>` into a move of the value if it can
+    /// be encoded as an immediate, and otherwise into a load of the value
+    /// from the literal pool
+    fn load_literal(&mut self, tokens: &[Token]) -> (OpCode, Vec<Token>) {
+        if !InstrParser::parse_suffix(OpCode::LDR, &tokens[0]).is_empty() {
+            panic!("Only LDR can load a literal.");
+        }
+        let cond = InstrParser::parse_cond(OpCode::LDR, &tokens[0]);
+
+        let expr = match ExprParser::parse(tokens, 4) {
+            Some((expr, next)) if next == tokens.len() => expr,
+            _ => panic!("Expected a single expression."),
+        };
+        let value = self.eval(&expr);
+        if value.target.is_none()
+            && !(-0x8000_0000..=0xffff_ffff).contains(&value.value) {
+            panic!("Value {} does not fit in 32 bits.", value.value);
+        }
+
+        let token = |token_type: TokenType, value: String| Token {
+            token_type,
+            value,
+            line: tokens[0].line,
+        };
+
+        // Constants are moved when they, or their complement, can be encoded
+        if self.is_constant(&expr) {
+            for (opcode, imm) in [(OpCode::MOV, value.value as u32),
+                (OpCode::MVN, !value.value as u32)] {
+                let imm = token(TokenType::Number, format!("#{}", imm));
+                if InstrParser::parse_imm(&imm).is_some() {
+                    let mnemonic = format!("{}{}", opcode, cond)
+                        .to_lowercase();
+                    return (opcode, vec![token(TokenType::Keyword, mnemonic),
+                        tokens[1].clone(), tokens[2].clone(), imm]);
+                }
+            }
+        }
+
+        // Identical values of a pool are shared by their loads
+        let section = self.current;
+        let idx = match self.pools.iter()
+            .position(|pool| pool.section == section) {
+            Some(idx) => idx,
+            None => {
+                self.pools.push(Pool { section, values: Vec::new(),
+                    loads: Vec::new() });
+                self.pools.len() - 1
+            },
+        };
+        let pool = &mut self.pools[idx];
+        let entry = match pool.values.iter().position(|other| {
+            other.value as u32 == value.value as u32
+                && other.target == value.target
+        }) {
+            Some(entry) => entry,
+            None => {
+                pool.values.push(value);
+                pool.values.len() - 1
+            },
+        };
+        pool.loads.push((self.n_literals, entry));
+
+        let addr = self.literals.get(self.n_literals)
+            .map_or(0, |literal| literal.addr);
+        self.n_literals += 1;
+
+        if self.pass == Pass::Emit
+            && InstrParser::pc_offset(addr, self.addr()).unsigned_abs() > 0xfff {
+            panic!("Literal pool out of range, place one closer with .ltorg.");
+        }
+
+        (OpCode::LDR, vec![tokens[0].clone(), tokens[1].clone(),
+            tokens[2].clone(), token(TokenType::Number, addr.to_string())])
+    }
+
+    fn instruction(&mut self, tokens: &[Token]) {
+        // We know that the first token must be a keyword which contains an
+        // opcode
+        let (opcode, tokens) = self.mnemonic(tokens);
+
+        // Literals are placed in a pool during the layout
+        let (opcode, tokens) = match tokens.get(3) {
+            Some(token) if opcode == OpCode::LDR
+                && token.token_type == TokenType::Equal => {
+                self.load_literal(&tokens)
+            },
+            _ => (opcode, tokens),
+        };
+        let tokens = &tokens[..];
+
+        // Instructions are 4 bytes long, ADRL being made of two of them.
+        // They are only encoded once the labels have an address.
+        if self.pass == Pass::Layout {
+            let size = if opcode == OpCode::ADRL { 8 } else { 4 };
+            self.emit(&vec![0; size], Mapping::Arm);
+            return;
+        }
+
+        let addr = self.addr();
+
+        // Branches to the symbols of other files and, in object files, to the
+        // labels of other sections are completed by the linker, the offset
+        // field holding the addend
+        let external = match opcode {
+            OpCode::B | OpCode::BL | OpCode::BLX => self.external(tokens, 1),
+            _ => None,
+        };
+        let tokens = match &external {
+            Some((_, addend)) => vec![tokens[0].clone(), Token {
+                token_type: TokenType::Number,
+                value: (addr as i64 + addend).to_string(),
+                line: tokens[0].line,
+            }],
+            None => self.resolve(tokens),
+        };
+
+        // Immediates which cannot be encoded may be encoded by the opposite
+        // instruction, such as MVN for MOV
+        let (opcode, tokens) = match DataProcParser::substitute(opcode, &tokens) {
+            Some((opcode, tokens)) if !self.options.strict => {
+                self.note = Some(Self::format(&tokens));
+                (opcode, tokens)
+            },
+            _ => (opcode, tokens),
+        };
+        let tokens = &tokens[..];
+        let labels = &self.labels;
+
+        if opcode == OpCode::ADR || opcode == OpCode::ADRL {
+            // ADR{cond} Rd,<expression>
+            // ADRL{cond} Rd,<expression>
+            for word in AdrParser::parse(opcode, tokens, labels, addr) {
+                self.emit(&word.to_le_bytes(), Mapping::Arm);
+            }
+            return;
+        }
+
+        // Now that we have our opcode, we match it to its parser and store
+        // the result
+        let parsed: u32 = match opcode {
+            OpCode::BX => {
+                // BX{cond} Rn
+                BrXchgParser::parse(opcode, tokens)
+            },
+            OpCode::B | OpCode::BL => {
+                // B{L}{cond} <expression>
+                BranchParser::parse(opcode, tokens, labels, addr)
+            }
+            OpCode::BLX => {
+                // BLX{cond} Rm
+                // BLX <expression>
+                match tokens.get(1) {
+                    Some(token) if token.token_type == TokenType::Keyword
+                        && InstrParser::parse_reg(token).is_some() => {
+                        BrXchgParser::parse(opcode, tokens)
+                    },
+                    _ => BranchParser::parse_exchange(opcode, tokens, labels,
+                        addr),
+                }
+            },
+            OpCode::CLZ => {
+                // CLZ{cond} Rd,Rm
+                ClzParser::parse(opcode, tokens)
+            },
+            OpCode::BKPT => {
+                // BKPT {<expression>}
+                BkptParser::parse(opcode, tokens)
+            },
+            OpCode::AND | OpCode::EOR | OpCode::SUB | OpCode::RSB |
+            OpCode::ADD | OpCode::ADC | OpCode::SBC | OpCode::RSC |
+            OpCode::TST | OpCode::TEQ | OpCode::CMP | OpCode::CMN |
+            OpCode::ORR | OpCode::MOV | OpCode::BIC | OpCode::MVN => {
+                // * MOV, MVN:
+                //      <opcode>{cond}{S} Rd,<Op2>
+                // * CMP, CMN, TEQ, TST:
+                //      <opcode>{cond} Rn,<Op2>
+                // * AND, EOR, SUB, RSB, ADD, ADC, SBC, RSC, ORR, BIC:
+                //      <opcode>{cond}{S} Rd,Rn,<Op2>
+                DataProcParser::parse(opcode, tokens)
+            },
+            OpCode::MRS | OpCode::MSR => {
+                // MRS{cond} Rd,<psr>
+                // MSR{cond} <psr>,Rm
+                // MSR{cond} <psrf>,Rm
+                // MSR{cond} <psrf>,<#expression>
+                PsrTransferParser::parse(opcode, tokens)
+            },
+            OpCode::MUL | OpCode::MLA | OpCode::MLS => {
+                // MUL{cond}{S} Rd,Rm,Rs
+                // MLA{cond}{S} Rd,Rm,Rs,Rn
+                // MLS{cond} Rd,Rm,Rs,Rn
+                MulParser::parse(opcode, tokens)
+            },
+            OpCode::UMULL | OpCode::UMLAL | OpCode::SMULL | OpCode::SMLAL => {
+                // UMULL{cond}{S} RdLo,RdHi,Rm,Rs
+                // UMLAL{cond}{S} RdLo,RdHi,Rm,Rs
+                // SMULL{cond}{S} RdLo,RdHi,Rm,Rs
+                // SMLAL{cond}{S} RdLo,RdHi,Rm,Rs
+                // SMLAL<x><y>{cond} RdLo,RdHi,Rm,Rs
+                MulLongParser::parse(opcode, tokens)
+            },
+            OpCode::SMLA | OpCode::SMLAW | OpCode::SMUL | OpCode::SMULW => {
+                // SMLA<x><y>{cond} Rd,Rm,Rs,Rn
+                // SMLAW<y>{cond} Rd,Rm,Rs,Rn
+                // SMUL<x><y>{cond} Rd,Rm,Rs
+                // SMULW<y>{cond} Rd,Rm,Rs
+                MulParser::parse_halfword(opcode, tokens)
+            },
+            OpCode::QADD | OpCode::QSUB | OpCode::QDADD | OpCode::QDSUB => {
+                // <QADD|QSUB|QDADD|QDSUB>{cond} Rd,Rm,Rn
+                SatArithParser::parse(opcode, tokens)
+            },
+            OpCode::LDR | OpCode::STR => {
+                // <LDR|STR>{cond}{B}{T} Rd,<address>
+                // <LDR|STR>{cond}<H|SH|SB|D> Rd,<address>
+                DataTransferParser::parse(opcode, tokens, labels, addr)
+            },
+            OpCode::PLD | OpCode::PLI => {
+                // <PLD|PLI> <address>
+                DataTransferParser::parse_preload(opcode, tokens, labels, addr)
+            },
+            OpCode::LDM | OpCode::STM => {
+                // <LDM|STM>{cond}<FD|ED|FA|EA|IA|IB|DA|DB> Rn{!},<Rlist>{^}
+                BlockTransferParser::parse(opcode, tokens)
+            },
+            OpCode::SWP => {
+                // <SWP>{cond}{B} Rd,Rm,[Rn]
+                DataSwapParser::parse(opcode, tokens)
+            },
+            OpCode::CDP | OpCode::CDP2 => {
+                // CDP{cond} p#,<expression1>,cd,cn,cm{,<expression2>}
+                // CDP2 p#,<expression1>,cd,cn,cm{,<expression2>}
+                CpOpsParser::parse(opcode, tokens)
+            },
+            OpCode::LDC | OpCode::STC | OpCode::LDC2 | OpCode::STC2 => {
+                // <LDC|STC>{cond}{L} p#,cd,<address>
+                // <LDC2|STC2>{L} p#,cd,<address>
+                CpTransfersParser::parse(opcode, tokens, labels, addr)
+            },
+            OpCode::MRC | OpCode::MCR | OpCode::MRC2 | OpCode::MCR2 => {
+                // <MCR|MRC>{cond} p#,<expression1>,Rd,cn,cm{,<expression2>}
+                // <MCR2|MRC2> p#,<expression1>,Rd,cn,cm{,<expression2>}
+                CpRegTransParser::parse(opcode, tokens)
+            }
+            OpCode::MCRR | OpCode::MRRC => {
+                // <MCRR|MRRC>{cond} p#,<expression>,Rd,Rn,cm
+                CpRegTransParser::parse_double(opcode, tokens)
+            }
+            OpCode::REV | OpCode::REV16 | OpCode::REVSH | OpCode::RBIT => {
+                // <REV|REV16|REVSH|RBIT>{cond} Rd,Rm
+                RevParser::parse(opcode, tokens)
+            },
+            OpCode::SXTB | OpCode::SXTH | OpCode::SXTB16 | OpCode::UXTB |
+            OpCode::UXTH | OpCode::UXTB16 => {
+                // <SXT|UXT><B|H|B16>{cond} Rd,Rm{,ROR #<8|16|24>}
+                ExtendParser::parse(opcode, tokens)
+            },
+            OpCode::SXTAB | OpCode::SXTAH | OpCode::SXTAB16 | OpCode::UXTAB |
+            OpCode::UXTAH | OpCode::UXTAB16 => {
+                // <SXTA|UXTA><B|H|B16>{cond} Rd,Rn,Rm{,ROR #<8|16|24>}
+                ExtendParser::parse(opcode, tokens)
+            },
+            OpCode::SSAT | OpCode::USAT | OpCode::SSAT16 | OpCode::USAT16 => {
+                // <SSAT|USAT>{cond} Rd,#<expression>,Rn{,<shift>}
+                // <SSAT16|USAT16>{cond} Rd,#<expression>,Rn
+                SatParser::parse(opcode, tokens)
+            },
+            OpCode::SEL | OpCode::PKHBT | OpCode::PKHTB => {
+                // SEL{cond} Rd,Rn,Rm
+                // PKHBT{cond} Rd,Rn,Rm{,LSL #<expression>}
+                // PKHTB{cond} Rd,Rn,Rm{,ASR #<expression>}
+                PackParser::parse(opcode, tokens)
+            },
+            OpCode::S | OpCode::Q | OpCode::SH | OpCode::U | OpCode::UQ |
+            OpCode::UH => {
+                // <S|Q|SH|U|UQ|UH><ADD16|ASX|SAX|SUB16|ADD8|SUB8>{cond}
+                //     Rd,Rn,Rm
+                ParallelParser::parse(opcode, tokens)
+            },
+            OpCode::USAD8 | OpCode::USADA8 => {
+                // USAD8{cond} Rd,Rm,Rs
+                // USADA8{cond} Rd,Rm,Rs,Rn
+                ParallelParser::parse_sad(opcode, tokens)
+            },
+            OpCode::SMUAD | OpCode::SMUSD | OpCode::SMLAD | OpCode::SMLSD |
+            OpCode::SMMUL | OpCode::SMMLA | OpCode::SMMLS => {
+                // <SMUAD|SMUSD>{X}{cond} Rd,Rm,Rs
+                // <SMLAD|SMLSD>{X}{cond} Rd,Rm,Rs,Rn
+                // SMMUL{R}{cond} Rd,Rm,Rs
+                // <SMMLA|SMMLS>{R}{cond} Rd,Rm,Rs,Rn
+                MulParser::parse_dual(opcode, tokens)
+            },
+            OpCode::SMLALD | OpCode::SMLSLD => {
+                // <SMLALD|SMLSLD>{X}{cond} RdLo,RdHi,Rm,Rs
+                MulLongParser::parse_dual(opcode, tokens)
+            },
+            OpCode::LDREX | OpCode::STREX => {
+                // LDREX{B|H}{cond} Rt,[Rn]
+                // LDREXD{cond} Rt,Rt2,[Rn]
+                // STREX{B|H}{cond} Rd,Rt,[Rn]
+                // STREXD{cond} Rd,Rt,Rt2,[Rn]
+                ExclusiveParser::parse(opcode, tokens)
+            },
+            OpCode::CLREX => {
+                // CLREX
+                ExclusiveParser::parse_clear(opcode, tokens)
+            },
+            OpCode::CPS | OpCode::CPSIE | OpCode::CPSID => {
+                // CPS #<mode>
+                // <CPSIE|CPSID> <a|i|f>{,#<mode>}
+                SystemParser::parse_cps(opcode, tokens)
+            },
+            OpCode::SETEND => {
+                // SETEND <BE|LE>
+                SystemParser::parse_setend(opcode, tokens)
+            },
+            OpCode::SRS | OpCode::RFE => {
+                // SRS{<FD|ED|FA|EA|IA|IB|DA|DB>} {SP{!},}#<mode>{!}
+                // RFE{<FD|ED|FA|EA|IA|IB|DA|DB>} Rn{!}
+                SystemParser::parse_return(opcode, tokens)
+            },
+            OpCode::YIELD | OpCode::WFE | OpCode::WFI | OpCode::SEV |
+            OpCode::DBG => {
+                // <YIELD|WFE|WFI|SEV>{cond}
+                // DBG{cond} #<option>
+                SystemParser::parse_hint(opcode, tokens)
+            },
+            OpCode::DMB | OpCode::DSB | OpCode::ISB => {
+                // <DMB|DSB|ISB> {<option>}
+                SystemParser::parse_barrier(opcode, tokens)
+            },
+            OpCode::MOVW | OpCode::MOVT => {
+                // <MOVW|MOVT>{cond} Rd,#<expression>
+                MovWideParser::parse(opcode, tokens)
+            },
+            OpCode::BFC | OpCode::BFI | OpCode::SBFX | OpCode::UBFX => {
+                // BFC{cond} Rd,#<lsb>,#<width>
+                // <BFI|SBFX|UBFX>{cond} Rd,Rn,#<lsb>,#<width>
+                BitfieldParser::parse(opcode, tokens)
+            },
+            OpCode::SWI => {
+                // <SWI|SVC>{cond} <expression>
+                SwiParser::parse(opcode, tokens)
+            },
+            _ => panic!("Opcode not handled yet."),
+        };
+
+        if let Some((target, _)) = external {
+            let sub = self.subsection();
+            sub.relocs.push(Reloc {
+                offset: sub.data.len() as u32,
+                target,
+                reloc_type: match opcode {
+                    OpCode::BL | OpCode::BLX => RelocType::Call,
+                    _ => RelocType::Jump24,
+                },
+            });
+        }
+
+        self.emit(&parsed.to_le_bytes(), Mapping::Arm);
+    }
+}
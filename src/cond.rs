@@ -26,21 +26,22 @@ impl FromStr for Cond {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
-            "eq" => Ok(Self::EQ),
-            "ne" => Ok(Self::NE),
-            "cs" => Ok(Self::CS),
-            "cc" => Ok(Self::CC),
-            "mi" => Ok(Self::MI),
-            "pl" => Ok(Self::PL),
-            "vs" => Ok(Self::VS),
-            "vc" => Ok(Self::VC),
-            "hi" => Ok(Self::HI),
-            "ls" => Ok(Self::LS),
-            "ge" => Ok(Self::GE),
-            "lt" => Ok(Self::LT),
-            "gt" => Ok(Self::GT),
-            "le" => Ok(Self::LE),
-            _    => Err(()),
+            "eq"        => Ok(Self::EQ),
+            "ne"        => Ok(Self::NE),
+            "cs" | "hs" => Ok(Self::CS),
+            "cc" | "lo" => Ok(Self::CC),
+            "mi"        => Ok(Self::MI),
+            "pl"        => Ok(Self::PL),
+            "vs"        => Ok(Self::VS),
+            "vc"        => Ok(Self::VC),
+            "hi"        => Ok(Self::HI),
+            "ls"        => Ok(Self::LS),
+            "ge"        => Ok(Self::GE),
+            "lt"        => Ok(Self::LT),
+            "gt"        => Ok(Self::GT),
+            "le"        => Ok(Self::LE),
+            "al"        => Ok(Self::AL),
+            _           => Err(()),
         }
     }
 }
@@ -0,0 +1,95 @@
+use std::str::FromStr;
+use std::fmt;
+
+/// List of available assembler directives
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Directive {
+    // Sections
+    Section,
+    Text,
+    Data,
+    Bss,
+    PushSection,
+    PopSection,
+    Previous,
+    SubSection,
+    // Alignment
+    Align,
+    BAlign,
+    // Data
+    Byte,
+    HWord,
+    Word,
+    ULeb128,
+    SLeb128,
+    Space,
+    // Zero-initialised storage
+    Comm,
+    LComm,
+    // Raw instructions
+    Inst,
+    InstN,
+    InstW,
+    // Symbols
+    Equ,
+    Global,
+    Extern,
+    // Conditional assembly
+    If,
+    ElseIf,
+    Else,
+    EndIf,
+    // End of the source
+    End,
+    // Syntax of the mnemonics
+    Syntax,
+    // Literal pools
+    Ltorg,
+}
+
+impl FromStr for Directive {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            ".section"             => Ok(Self::Section),
+            ".text"                => Ok(Self::Text),
+            ".data"                => Ok(Self::Data),
+            ".bss"                 => Ok(Self::Bss),
+            ".pushsection"         => Ok(Self::PushSection),
+            ".popsection"          => Ok(Self::PopSection),
+            ".previous"            => Ok(Self::Previous),
+            ".subsection"          => Ok(Self::SubSection),
+            ".align"               => Ok(Self::Align),
+            ".balign"              => Ok(Self::BAlign),
+            ".byte"                => Ok(Self::Byte),
+            ".hword" | ".short"    => Ok(Self::HWord),
+            ".word" | ".long"      => Ok(Self::Word),
+            ".uleb128"             => Ok(Self::ULeb128),
+            ".sleb128"             => Ok(Self::SLeb128),
+            ".space" | ".skip"     => Ok(Self::Space),
+            ".comm"                => Ok(Self::Comm),
+            ".lcomm"               => Ok(Self::LComm),
+            ".inst"                => Ok(Self::Inst),
+            ".inst.n"              => Ok(Self::InstN),
+            ".inst.w"              => Ok(Self::InstW),
+            ".equ" | ".set"        => Ok(Self::Equ),
+            ".global" | ".globl"   => Ok(Self::Global),
+            ".extern"              => Ok(Self::Extern),
+            ".if"                  => Ok(Self::If),
+            ".elseif"              => Ok(Self::ElseIf),
+            ".else"                => Ok(Self::Else),
+            ".endif"               => Ok(Self::EndIf),
+            ".end"                 => Ok(Self::End),
+            ".syntax"              => Ok(Self::Syntax),
+            ".ltorg" | ".pool"     => Ok(Self::Ltorg),
+            _                      => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for Directive {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
@@ -0,0 +1,333 @@
+use crate::assembler::Assembler;
+use crate::reloc::RelocTarget;
+use crate::section::Mapping;
+
+// Values from the ELF specification and the ELF for the ARM architecture
+const ET_REL: u16 = 1;
+const EM_ARM: u16 = 40;
+/// Version 5 of the ARM EABI
+const EF_ARM_EABI_VER5: u32 = 0x0500_0000;
+
+const SHT_PROGBITS: u32 = 1;
+const SHT_SYMTAB: u32 = 2;
+const SHT_STRTAB: u32 = 3;
+const SHT_NOBITS: u32 = 8;
+const SHT_REL: u32 = 9;
+
+const SHF_WRITE: u32 = 0x1;
+const SHF_ALLOC: u32 = 0x2;
+const SHF_EXECINSTR: u32 = 0x4;
+const SHF_INFO_LINK: u32 = 0x40;
+
+const STB_LOCAL: u8 = 0;
+const STB_GLOBAL: u8 = 1;
+const STT_NOTYPE: u8 = 0;
+const STT_OBJECT: u8 = 1;
+const STT_SECTION: u8 = 3;
+
+const SHN_UNDEF: u16 = 0;
+const SHN_COMMON: u16 = 0xfff2;
+
+/// Size of the ELF header
+const EHDR_SIZE: u32 = 52;
+/// Size of a section header
+const SHDR_SIZE: u32 = 40;
+/// Size of a symbol table entry
+const SYM_SIZE: u32 = 16;
+/// Size of a relocation entry
+const REL_SIZE: u32 = 8;
+
+/// Entry of the symbol table
+struct Symbol {
+    name: String,
+    value: u32,
+    size: u32,
+    /// Binding (upper 4 bits) and type (lower 4 bits)
+    info: u8,
+    /// Index of the section in which the symbol is defined
+    shndx: u16,
+}
+
+/// Section of the object file, along with its content
+struct ElfSection {
+    name: String,
+    sh_type: u32,
+    flags: u32,
+    link: u32,
+    info: u32,
+    align: u32,
+    entsize: u32,
+    /// Content of the section, empty for `SHT_NOBITS` sections
+    data: Vec<u8>,
+    size: u32,
+}
+
+impl ElfSection {
+    fn new(name: &str, sh_type: u32, data: Vec<u8>) -> Self {
+        Self {
+            name: name.to_string(),
+            sh_type,
+            flags: 0,
+            link: 0,
+            info: 0,
+            align: 1,
+            entsize: 0,
+            size: data.len() as u32,
+            data,
+        }
+    }
+}
+
+/// Table of null-terminated strings
+struct StringTable {
+    data: Vec<u8>,
+}
+
+impl StringTable {
+    fn new() -> Self {
+        // The first string is always the empty string
+        Self { data: vec![0] }
+    }
+
+    /// Add `s` to the table and return its offset
+    fn add(&mut self, s: &str) -> u32 {
+        if s.is_empty() {
+            return 0;
+        }
+
+        let offset = self.data.len() as u32;
+        self.data.extend(s.as_bytes());
+        self.data.push(0);
+        offset
+    }
+}
+
+pub struct ElfWriter;
+
+impl ElfWriter {
+    /// Return the ELF relocatable object made of the assembled sections
+    pub fn write(assembler: &Assembler) -> Vec<u8> {
+        let sections = &assembler.sections;
+        // Index of the section header of each assembled section, the first
+        // header being the null one
+        let shndx = |section: usize| (section + 1) as u16;
+
+        // Local symbols come first, starting with the null symbol
+        let mut symbols = vec![Symbol { name: String::new(), value: 0,
+            size: 0, info: 0, shndx: 0 }];
+
+        // Section symbols, used by the relocations against local labels
+        for i in 0..sections.len() {
+            symbols.push(Symbol { name: String::new(), value: 0, size: 0,
+                info: STB_LOCAL << 4 | STT_SECTION, shndx: shndx(i) });
+        }
+
+        // Mapping symbols, telling disassemblers where code and data are
+        for (i, section) in sections.iter().enumerate() {
+            for sub in section.subsections.values() {
+                for &(offset, mapping) in &sub.mappings {
+                    let name = match mapping {
+                        Mapping::Arm => "$a",
+                        Mapping::Thumb => "$t",
+                        Mapping::Data => "$d",
+                    };
+                    symbols.push(Symbol { name: name.to_string(),
+                        value: sub.offset + offset, size: 0,
+                        info: STB_LOCAL << 4 | STT_NOTYPE, shndx: shndx(i) });
+                }
+            }
+        }
+
+        // Local labels are only known by the assembler
+        for label in assembler.labels.iter().filter(|label| !label.is_local()
+            && !assembler.globals.contains(&label.name)) {
+            let section = label.section.section;
+            symbols.push(Symbol { name: label.name.clone(),
+                value: label.addr - sections[section].addr, size: 0,
+                info: STB_LOCAL << 4 | STT_NOTYPE, shndx: shndx(section) });
+        }
+
+        let first_global = symbols.len();
+
+        // Common symbols hold their alignment instead of an address
+        for common in &assembler.commons {
+            symbols.push(Symbol { name: common.name.clone(),
+                value: common.align, size: common.size,
+                info: STB_GLOBAL << 4 | STT_OBJECT, shndx: SHN_COMMON });
+        }
+
+        // Global labels, and the symbols left undefined for the linker
+        let undefined = assembler.externs.iter()
+            .filter(|name| !assembler.globals.contains(name)
+                && !assembler.labels.iter().any(|label| &&label.name == name));
+        for name in assembler.globals.iter().chain(undefined) {
+            if assembler.commons.iter().any(|common| &common.name == name) {
+                continue;
+            }
+
+            let symbol = match assembler.labels.iter()
+                .find(|label| &label.name == name) {
+                Some(label) => {
+                    let section = label.section.section;
+                    Symbol { name: name.clone(),
+                        value: label.addr - sections[section].addr, size: 0,
+                        info: STB_GLOBAL << 4 | STT_NOTYPE,
+                        shndx: shndx(section) }
+                },
+                None => Symbol { name: name.clone(), value: 0, size: 0,
+                    info: STB_GLOBAL << 4 | STT_NOTYPE, shndx: SHN_UNDEF },
+            };
+            symbols.push(symbol);
+        }
+
+        // Index of the symbol table header, after the assembled sections and
+        // their relocation sections
+        let n_rel = sections.iter()
+            .filter(|section| section.subsections.values()
+                .any(|sub| !sub.relocs.is_empty()))
+            .count();
+        let symtab_idx = (sections.len() + n_rel + 1) as u32;
+
+        let mut elf_sections = Vec::new();
+        let mut rel_sections = Vec::new();
+
+        for (i, section) in sections.iter().enumerate() {
+            let name = &section.name;
+            let (sh_type, flags) = if let Some(attrs) = section.flags {
+                let mut flags = 0;
+                if attrs.alloc { flags |= SHF_ALLOC; }
+                if attrs.write { flags |= SHF_WRITE; }
+                if attrs.exec { flags |= SHF_EXECINSTR; }
+                match attrs.nobits {
+                    true => (SHT_NOBITS, flags),
+                    false => (SHT_PROGBITS, flags),
+                }
+            } else if name == ".bss"
+                || name.starts_with(".bss.") {
+                (SHT_NOBITS, SHF_ALLOC | SHF_WRITE)
+            } else if name == ".text" || name.starts_with(".text.") {
+                (SHT_PROGBITS, SHF_ALLOC | SHF_EXECINSTR)
+            } else if name == ".data" || name.starts_with(".data.") {
+                (SHT_PROGBITS, SHF_ALLOC | SHF_WRITE)
+            } else {
+                (SHT_PROGBITS, SHF_ALLOC)
+            };
+
+            let mut elf_section = ElfSection::new(name, sh_type,
+                section.data());
+            elf_section.flags = flags;
+            elf_section.align = section.align();
+            if sh_type == SHT_NOBITS {
+                elf_section.data.clear();
+            }
+            elf_sections.push(elf_section);
+
+            // Relocations of the section
+            let mut rel = Vec::new();
+            for sub in section.subsections.values() {
+                for reloc in &sub.relocs {
+                    let sym = match &reloc.target {
+                        RelocTarget::Section(section) => *section + 1,
+                        RelocTarget::Symbol(name) => symbols.iter()
+                            .skip(first_global)
+                            .position(|sym| &sym.name == name)
+                            .expect("Relocation against an unknown symbol.")
+                            + first_global,
+                    } as u32;
+
+                    rel.extend((sub.offset + reloc.offset).to_le_bytes());
+                    rel.extend((sym << 8 | reloc.reloc_type as u32)
+                        .to_le_bytes());
+                }
+            }
+
+            if !rel.is_empty() {
+                let mut rel_section = ElfSection::new(
+                    &format!(".rel{}", name), SHT_REL, rel);
+                rel_section.flags = SHF_INFO_LINK;
+                rel_section.link = symtab_idx;
+                rel_section.info = shndx(i) as u32;
+                rel_section.align = 4;
+                rel_section.entsize = REL_SIZE;
+                rel_sections.push(rel_section);
+            }
+        }
+        elf_sections.extend(rel_sections);
+
+        // Symbol table and its string table
+        let mut strtab = StringTable::new();
+        let mut symtab = Vec::new();
+        for symbol in &symbols {
+            symtab.extend(strtab.add(&symbol.name).to_le_bytes());
+            symtab.extend(symbol.value.to_le_bytes());
+            symtab.extend(symbol.size.to_le_bytes());
+            symtab.push(symbol.info);
+            symtab.push(0);
+            symtab.extend(symbol.shndx.to_le_bytes());
+        }
+
+        let mut symtab = ElfSection::new(".symtab", SHT_SYMTAB, symtab);
+        symtab.link = symtab_idx + 1;
+        symtab.info = first_global as u32;
+        symtab.align = 4;
+        symtab.entsize = SYM_SIZE;
+        elf_sections.push(symtab);
+        elf_sections.push(ElfSection::new(".strtab", SHT_STRTAB, strtab.data));
+
+        // Section names, the table holding them being the last section
+        let mut shstrtab = StringTable::new();
+        let mut names: Vec<u32> = elf_sections.iter()
+            .map(|section| shstrtab.add(&section.name))
+            .collect();
+        names.push(shstrtab.add(".shstrtab"));
+        elf_sections.push(ElfSection::new(".shstrtab", SHT_STRTAB,
+            shstrtab.data));
+
+        // The content of the sections follows the ELF header, and the section
+        // headers come last
+        let mut out = vec![0u8; EHDR_SIZE as usize];
+        let mut offsets = Vec::new();
+        for section in &elf_sections {
+            out.resize((out.len() as u32).next_multiple_of(section.align)
+                as usize, 0);
+            offsets.push(out.len() as u32);
+            out.extend(&section.data);
+        }
+        out.resize(out.len().next_multiple_of(4), 0);
+        let shoff = out.len() as u32;
+
+        // Null section header
+        out.extend([0; SHDR_SIZE as usize]);
+        for (i, section) in elf_sections.iter().enumerate() {
+            for field in [names[i], section.sh_type, section.flags, 0,
+                offsets[i], section.size, section.link, section.info,
+                section.align, section.entsize] {
+                out.extend(field.to_le_bytes());
+            }
+        }
+
+        // ELF header
+        let mut ehdr = Vec::new();
+        // Magic number, 32-bit, little endian, version 1
+        ehdr.extend(b"\x7fELF\x01\x01\x01");
+        ehdr.resize(16, 0);
+        ehdr.extend(ET_REL.to_le_bytes());
+        ehdr.extend(EM_ARM.to_le_bytes());
+        // Version, entry point and program header offset
+        ehdr.extend(1u32.to_le_bytes());
+        ehdr.extend(0u32.to_le_bytes());
+        ehdr.extend(0u32.to_le_bytes());
+        ehdr.extend(shoff.to_le_bytes());
+        ehdr.extend(EF_ARM_EABI_VER5.to_le_bytes());
+        ehdr.extend((EHDR_SIZE as u16).to_le_bytes());
+        // No program headers
+        ehdr.extend(0u16.to_le_bytes());
+        ehdr.extend(0u16.to_le_bytes());
+        ehdr.extend((SHDR_SIZE as u16).to_le_bytes());
+        ehdr.extend(((elf_sections.len() + 1) as u16).to_le_bytes());
+        ehdr.extend((elf_sections.len() as u16).to_le_bytes());
+        out[..EHDR_SIZE as usize].copy_from_slice(&ehdr);
+
+        out
+    }
+}
@@ -0,0 +1,243 @@
+use std::str::FromStr;
+
+use crate::token::{Token, TokenType};
+use crate::reloc::RelocTarget;
+use crate::register::{Register, CoRegister};
+use crate::shift::Shift;
+use crate::psrf::PSRF;
+use crate::cpn::CPN;
+
+/// Operators taking one operand
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum UnaryOp {
+    /// -
+    Neg,
+    /// ~
+    Not,
+    /// !
+    LogicalNot,
+}
+
+/// Operators taking two operands
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BinaryOp {
+    Mul,
+    Div,
+    Rem,
+    Add,
+    Sub,
+    Shl,
+    Shr,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+    And,
+    Xor,
+    Or,
+    LogicalAnd,
+    LogicalOr,
+}
+
+impl BinaryOp {
+    /// Binary operators from the lowest to the highest precedence, as in C
+    const PRECEDENCE: [&'static [(TokenType, BinaryOp)]; 10] = [
+        &[(TokenType::OrOr, BinaryOp::LogicalOr)],
+        &[(TokenType::AndAnd, BinaryOp::LogicalAnd)],
+        &[(TokenType::Pipe, BinaryOp::Or)],
+        &[(TokenType::Caret, BinaryOp::Xor)],
+        &[(TokenType::Ampersand, BinaryOp::And)],
+        &[(TokenType::EqualEqual, BinaryOp::Eq),
+            (TokenType::NotEqual, BinaryOp::Ne)],
+        &[(TokenType::Less, BinaryOp::Lt), (TokenType::LessEqual, BinaryOp::Le),
+            (TokenType::Greater, BinaryOp::Gt),
+            (TokenType::GreaterEqual, BinaryOp::Ge)],
+        &[(TokenType::ShiftLeft, BinaryOp::Shl),
+            (TokenType::ShiftRight, BinaryOp::Shr)],
+        &[(TokenType::Plus, BinaryOp::Add), (TokenType::Minus, BinaryOp::Sub)],
+        &[(TokenType::Star, BinaryOp::Mul), (TokenType::Slash, BinaryOp::Div),
+            (TokenType::Percent, BinaryOp::Rem)],
+    ];
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    Number(i64),
+    /// Label or constant symbol, along with the line it is used on
+    Symbol(String, usize),
+    /// Current location, written '.'
+    Location,
+    Unary(UnaryOp, Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+}
+
+/// Result of an expression: a number, to which the linker may have to add the
+/// address of a symbol
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Value {
+    pub value: i64,
+    pub target: Option<RelocTarget>,
+}
+
+impl Value {
+    pub fn new(value: i64) -> Self {
+        Self { value, target: None }
+    }
+
+    /// Apply `op` on `self`, the value must not be relocatable
+    pub fn unary(self, op: UnaryOp) -> Result<Value, String> {
+        if self.target.is_some() {
+            return Err("Expression cannot be relocated.".to_string());
+        }
+
+        Ok(Value::new(match op {
+            UnaryOp::Neg => self.value.wrapping_neg(),
+            UnaryOp::Not => !self.value,
+            UnaryOp::LogicalNot => (self.value == 0) as i64,
+        }))
+    }
+
+    /// Apply `op` on `self` and `rhs`. A relocatable value may only be offset
+    /// by a number, or subtracted from a value relative to the same symbol,
+    /// which gives a number.
+    pub fn binary(self, op: BinaryOp, rhs: Value) -> Result<Value, String> {
+        let target = match (op, self.target, rhs.target) {
+            (_, None, None) => None,
+            (BinaryOp::Add, Some(target), None)
+            | (BinaryOp::Add, None, Some(target))
+            | (BinaryOp::Sub, Some(target), None) => Some(target),
+            (BinaryOp::Sub, Some(lhs), Some(rhs)) if lhs == rhs => None,
+            _ => return Err("Expression cannot be relocated.".to_string()),
+        };
+
+        let (lhs, rhs) = (self.value, rhs.value);
+        // Comparisons give -1 (all bits set) when true, as in GNU as
+        let value = match op {
+            BinaryOp::Mul => lhs.wrapping_mul(rhs),
+            BinaryOp::Div | BinaryOp::Rem if rhs == 0 => {
+                return Err("Division by zero.".to_string());
+            },
+            BinaryOp::Div => lhs.wrapping_div(rhs),
+            BinaryOp::Rem => lhs.wrapping_rem(rhs),
+            BinaryOp::Add => lhs.wrapping_add(rhs),
+            BinaryOp::Sub => lhs.wrapping_sub(rhs),
+            BinaryOp::Shl => lhs.checked_shl(rhs as u32).unwrap_or(0),
+            BinaryOp::Shr => lhs.checked_shr(rhs as u32).unwrap_or(0),
+            BinaryOp::Lt => -((lhs < rhs) as i64),
+            BinaryOp::Le => -((lhs <= rhs) as i64),
+            BinaryOp::Gt => -((lhs > rhs) as i64),
+            BinaryOp::Ge => -((lhs >= rhs) as i64),
+            BinaryOp::Eq => -((lhs == rhs) as i64),
+            BinaryOp::Ne => -((lhs != rhs) as i64),
+            BinaryOp::And => lhs & rhs,
+            BinaryOp::Xor => lhs ^ rhs,
+            BinaryOp::Or => lhs | rhs,
+            BinaryOp::LogicalAnd => (lhs != 0 && rhs != 0) as i64,
+            BinaryOp::LogicalOr => (lhs != 0 || rhs != 0) as i64,
+        };
+
+        Ok(Value { value, target })
+    }
+}
+
+pub struct ExprParser<'a> {
+    tokens: &'a [Token],
+    /// Index of the next token to parse
+    pos: usize,
+}
+
+impl<'a> ExprParser<'a> {
+    /// Parse the expression starting at index `start`, and return it along
+    /// with the index of the token following it. An optional '#' may precede
+    /// the expression.
+    pub fn parse(tokens: &'a [Token], start: usize) -> Option<(Expr, usize)> {
+        let mut parser = Self { tokens, pos: start };
+        if parser.next_is(TokenType::Hash) {
+            parser.pos += 1;
+        }
+
+        let expr = parser.parse_binary(0)?;
+        Some((expr, parser.pos))
+    }
+
+    /// Return true if `token` may name a symbol, which excludes the names of
+    /// registers, shifts, PSR formats and coprocessor numbers
+    pub fn is_symbol(token: &Token) -> bool {
+        let name = token.value.as_str();
+        match token.token_type {
+            TokenType::Keyword => Register::from_str(name).is_err()
+                && CoRegister::from_str(name).is_err()
+                && Shift::from_str(name).is_err()
+                && PSRF::from_str(name).is_err()
+                && CPN::from_str(name).is_err(),
+            // Local labels starting with a dot
+            TokenType::Directive => name != ".",
+            _ => false,
+        }
+    }
+
+    fn next_is(&self, token_type: TokenType) -> bool {
+        self.tokens.get(self.pos)
+            .is_some_and(|token| token.token_type == token_type)
+    }
+
+    /// Parse the binary operators of precedence `level` and above
+    fn parse_binary(&mut self, level: usize) -> Option<Expr> {
+        if level == BinaryOp::PRECEDENCE.len() {
+            return self.parse_unary();
+        }
+
+        let mut lhs = self.parse_binary(level + 1)?;
+        while let Some(&(_, op)) = BinaryOp::PRECEDENCE[level].iter()
+            .find(|(token_type, _)| self.next_is(*token_type)) {
+            self.pos += 1;
+            let rhs = self.parse_binary(level + 1)?;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+
+        Some(lhs)
+    }
+
+    fn parse_unary(&mut self) -> Option<Expr> {
+        let token = self.tokens.get(self.pos)?;
+        let op = match token.token_type {
+            TokenType::Minus => Some(UnaryOp::Neg),
+            TokenType::Tilde => Some(UnaryOp::Not),
+            TokenType::Exclamation => Some(UnaryOp::LogicalNot),
+            TokenType::Plus => None,
+            _ => return self.parse_primary(),
+        };
+
+        self.pos += 1;
+        let expr = self.parse_unary()?;
+        Some(match op {
+            Some(op) => Expr::Unary(op, Box::new(expr)),
+            None => expr,
+        })
+    }
+
+    fn parse_primary(&mut self) -> Option<Expr> {
+        let token = self.tokens.get(self.pos)?;
+        self.pos += 1;
+
+        match token.token_type {
+            TokenType::Number => Some(Expr::Number(token.number()
+                .unwrap_or_else(|err| panic!("{}", err)))),
+            TokenType::Directive if token.value == "." => Some(Expr::Location),
+            _ if Self::is_symbol(token) => {
+                Some(Expr::Symbol(token.value.clone(), token.line))
+            },
+            TokenType::OpenParen => {
+                let expr = self.parse_binary(0)?;
+                if !self.next_is(TokenType::CloseParen) {
+                    return None;
+                }
+                self.pos += 1;
+                Some(expr)
+            },
+            _ => None,
+        }
+    }
+}
@@ -1,48 +1,96 @@
-use crate::token::Token;
+use crate::section::SectionRef;
 
 #[derive(Debug)]
 pub struct Label {
     pub name: String,
     pub line: usize,
+    /// Subsection in which the label is defined
+    pub section: SectionRef,
+    /// Offset of the label from the start of its subsection
+    pub offset: u32,
+    /// Absolute address of the label, known once the layout is done
+    pub addr: u32,
+    /// Label opening the scope of a cheap local label
+    pub scope: Option<String>,
 }
 
-impl From<&Token> for Label {
-    fn from(token: &Token) -> Self {
+impl Label {
+    pub fn new(name: &str, line: usize, section: SectionRef, offset: u32)
+        -> Self {
         Self {
-            // Remove the trailing ':' char
-            name: token.value[..token.value.len() - 1].to_string(),
-            line: token.line,
+            name: name.to_string(),
+            line,
+            section,
+            offset,
+            addr: 0,
+            scope: None,
         }
     }
-}
 
-impl Label {
-    /// Calculate the offset of `n_bits` bits between the label and an
-    /// instruction
-    pub fn offset(&self, instr: &Token, n_bits: usize) -> u32 {
-        match n_bits {
-            24 => {
-                // Label before instruction
-                if instr.line > self.line {
-                    return (self.line - instr.line) as u32 & 0xffffff;
-                }
-                // Label after instruction
-                return (self.line - (instr.line + 2)) as u32 & 0xffffff;
-            },
-            8 => {
-                return ((self.line - instr.line) as i32).abs() as u32 & 0xff;
-            },
-            12 => {
-                // Label before instruction
-                if instr.line > self.line {
-                    return ((self.line - instr.line * 4) as i32).abs()
-                        as u32 & 0xfff;
-                }
-                // Label after instruction
-                return (((self.line - instr.line - 2) * 4) as i32).abs()
-                    as u32 & 0xfff;
-            },
-            _ => unreachable!(),
-        };
+    /// Return true if `name` is the name of a numeric local label, such as
+    /// "1", which can be defined several times
+    pub fn is_numeric(name: &str) -> bool {
+        !name.is_empty() && name.chars().all(|chr| chr.is_ascii_digit())
+    }
+
+    /// Return true if `name` is the name of an assembler-local label, such
+    /// as ".Lloop", which never reaches the symbol table of an object file
+    pub fn is_assembler_local(name: &str) -> bool {
+        name.starts_with(".L")
     }
- }
+
+    /// Return true if `name` is the name of a cheap local label, such as
+    /// ".loop", which is only visible between the two non-local labels
+    /// around it
+    pub fn is_cheap_local(name: &str) -> bool {
+        name.starts_with('.') && !Label::is_assembler_local(name)
+    }
+
+    /// Return true if the label never reaches the symbol table of an object
+    /// file
+    pub fn is_local(&self) -> bool {
+        Label::is_numeric(&self.name)
+            || Label::is_assembler_local(&self.name)
+            || Label::is_cheap_local(&self.name)
+    }
+
+    /// Return the last non-local label defined before or on the line `line`,
+    /// which opens the scope of the cheap local labels that follow it
+    pub fn scope(labels: &[Label], line: usize) -> Option<&Label> {
+        labels.iter()
+            .rev()
+            .find(|label| label.line <= line && !label.is_local())
+    }
+
+    /// Return the label that `name` refers to from the line `line`.
+    ///
+    /// Numeric local labels are referred to by their number followed by 'b',
+    /// for the closest definition before or on the line, or by 'f', for the
+    /// closest definition after the line. Cheap local labels are looked for
+    /// in the scope of the line.
+    pub fn find<'a>(labels: &'a [Label], name: &str, line: usize)
+        -> Option<&'a Label> {
+        if Label::is_cheap_local(name) {
+            let scope = Label::scope(labels, line)
+                .map(|scope| &scope.name);
+            return labels.iter()
+                .find(|label| label.name == name
+                    && label.scope.as_ref() == scope);
+        }
+
+        let (number, direction) = name.split_at(name.len().saturating_sub(1));
+
+        if Label::is_numeric(number) {
+            match direction {
+                "b" => return labels.iter()
+                    .rev()
+                    .find(|label| label.name == number && label.line <= line),
+                "f" => return labels.iter()
+                    .find(|label| label.name == number && label.line > line),
+                _ => {},
+            }
+        }
+
+        labels.iter().find(|label| label.name == name)
+    }
+}
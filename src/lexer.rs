@@ -34,15 +34,15 @@ impl<'a> Lexer<'a> {
         while let Some(chr) = self.peek(0) {
             self.cursor.token_pos = self.cursor.pos;
 
-            if chr.is_ascii_alphabetic() || chr == '_' {
+            if chr.is_ascii_alphabetic() || chr == '_' || chr == '.' {
                 self.get_keyword_token();
-            } else if chr == '#' || chr.is_ascii_digit() {
+            } else if self.is_number(0) || (chr == '#' && self.is_number(1)) {
                 self.get_number_token();
-            } else if chr == '!' || chr == '+' || chr == '-' || chr == '['
-                    || chr == ']' || chr == '{' || chr == '}' || chr == ','
-                    || chr == '^' || chr == '\n' {
+            } else if chr == '"' {
+                self.get_string_token();
+            } else if "!+-[]{},^#*/%&|~()=<>\n".contains(chr) {
                 self.get_symbol_token();
-            } else if chr == ' ' {
+            } else if chr == ' ' || chr == '\t' || chr == '\r' {
                 // Do nothing
             } else {
                 panic!("Invalid char '{}' found while lexing.", chr);
@@ -60,6 +60,18 @@ impl<'a> Lexer<'a> {
         Some(self.input.as_bytes()[self.cursor.pos + n] as char)
     }
 
+    /// Return true if a number starts at the next nth character. Numbers
+    /// start with a digit or a quote, and may be signed after a '#'.
+    fn is_number(&self, n: usize) -> bool {
+        let start = |chr: Option<char>| chr
+            .is_some_and(|chr| chr.is_ascii_digit() || chr == '\'');
+
+        match self.peek(n) {
+            Some('-' | '+') if n > 0 => start(self.peek(n + 1)),
+            chr => start(chr),
+        }
+    }
+
     /// Return the input in the range `range` plus the current position of the
     /// cursor
     fn peek_range(&self, range: Range<usize>) -> Option<&str> {
@@ -89,7 +101,7 @@ impl<'a> Lexer<'a> {
     fn get_keyword_token(&mut self) {
         // Step through the entire keyword
         while let Some(chr) = self.peek(1) {
-            if chr.is_ascii_alphabetic() || chr.is_ascii_digit() || chr == '_' {
+            if chr.is_ascii_alphanumeric() || chr == '_' || chr == '.' {
                 self.step();
             } else if chr == ':' {
                 // If the keyword contains ':', we assume its a label
@@ -101,45 +113,115 @@ impl<'a> Lexer<'a> {
                 break;
             }
         }
-        self.push_token(TokenType::Keyword);
+
+        // Keywords starting with a dot are assembler directives
+        if self.input.as_bytes()[self.cursor.token_pos] == b'.' {
+            self.push_token(TokenType::Directive);
+        } else {
+            self.push_token(TokenType::Keyword);
+        }
     }
 
     fn get_number_token(&mut self) {
-        // Skip '#'
+        // Skip '#' and the sign
         if self.peek(0).unwrap() == '#' {
             self.step();
         }
+        if matches!(self.peek(0), Some('-' | '+')) {
+            self.step();
+        }
+
+        if self.peek(0).unwrap() == '\'' {
+            // Character constant, up to the closing quote
+            while let Some(chr) = self.peek(1) {
+                if chr == '\n' {
+                    break;
+                }
 
-        let mut is_hex = false;
-        // If the number is in hex format
-        if let Some(s) = self.peek_range(0..2) {
-            if s == "0x" {
-                is_hex = true;
-                // Skip "0"
-                self.step();
-                // Skip "x"
                 self.step();
+                match chr {
+                    // Skip the escaped character
+                    '\\' if self.peek(1).is_some_and(|chr| chr != '\n') => {
+                        self.step();
+                    },
+                    '\'' => break,
+                    _ => {},
+                }
             }
+
+            self.push_token(TokenType::Number);
+            return;
         }
 
+        // Step through the digits, along with the radix prefix and the '_'
+        // separators. Invalid digits are reported when the number is parsed.
         while let Some(chr) = self.peek(1) {
-            // Accept hex digit if the number is in hex format
-            let cond = match is_hex {
-                true => chr.is_ascii_hexdigit(),
-                false => chr.is_ascii_digit(),
-            };
-
-            if cond {
+            if chr.is_ascii_alphanumeric() || chr == '_' {
                 self.step();
             } else {
                 // End of the token
                 break;
             }
         }
+
+        // Decimal numbers without '#' may also be numeric local labels
+        let value = &self.input[self.cursor.token_pos..self.cursor.pos + 1];
+        if value.bytes().all(|chr| chr.is_ascii_digit())
+            && self.peek(1) == Some(':') {
+            // Definition, such as "1:"
+            self.step();
+            self.push_token(TokenType::Label);
+            return;
+        }
+
+        if let Some(n) = value.strip_suffix(['b', 'f']) {
+            // Reference to the closest definition backward or forward, such
+            // as "1b" or "1f"
+            if !n.is_empty() && n.bytes().all(|chr| chr.is_ascii_digit()) {
+                self.push_token(TokenType::Keyword);
+                return;
+            }
+        }
+
         self.push_token(TokenType::Number);
     }
 
+    /// Lex a string between double quotes, which may not span several lines
+    fn get_string_token(&mut self) {
+        while let Some(chr) = self.peek(1) {
+            if chr == '\n' {
+                break;
+            }
+
+            self.step();
+            if chr == '"' {
+                self.push_token(TokenType::String);
+                return;
+            }
+        }
+
+        panic!("Missing closing quote found while lexing.");
+    }
+
     fn get_symbol_token(&mut self) {
+        // Symbols made of two characters
+        let ty = match self.peek_range(0..2) {
+            Some("<<") => Some(TokenType::ShiftLeft),
+            Some(">>") => Some(TokenType::ShiftRight),
+            Some("<=") => Some(TokenType::LessEqual),
+            Some(">=") => Some(TokenType::GreaterEqual),
+            Some("==") => Some(TokenType::EqualEqual),
+            Some("!=") => Some(TokenType::NotEqual),
+            Some("&&") => Some(TokenType::AndAnd),
+            Some("||") => Some(TokenType::OrOr),
+            _          => None,
+        };
+        if let Some(ty) = ty {
+            self.step();
+            self.push_token(ty);
+            return;
+        }
+
         let ty = match self.peek(0).unwrap() {
             '!'  => TokenType::Exclamation,
             '+'  => TokenType::Plus,
@@ -150,11 +232,26 @@ impl<'a> Lexer<'a> {
             '}'  => TokenType::CloseCurlyBrace,
             ','  => TokenType::Comma,
             '^'  => TokenType::Caret,
-            // Ignore useless endlines
-            '\n' if self.cursor.pos != 0 => {
+            '#'  => TokenType::Hash,
+            '*'  => TokenType::Star,
+            '/'  => TokenType::Slash,
+            '%'  => TokenType::Percent,
+            '&'  => TokenType::Ampersand,
+            '|'  => TokenType::Pipe,
+            '~'  => TokenType::Tilde,
+            '('  => TokenType::OpenParen,
+            ')'  => TokenType::CloseParen,
+            '='  => TokenType::Equal,
+            '<'  => TokenType::Less,
+            '>'  => TokenType::Greater,
+            '\n' => {
                 self.cursor.line += 1;
+                // Ignore useless endlines
+                if self.cursor.pos == 0 {
+                    return;
+                }
                 TokenType::Endline
-            }
+            },
             _    => return,
         };
         self.push_token(ty);
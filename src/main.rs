@@ -9,37 +9,75 @@ pub mod shift;
 pub mod psrf;
 pub mod cpn;
 pub mod label;
+pub mod directive;
+pub mod section;
+pub mod assembler;
+pub mod options;
+pub mod reloc;
+pub mod elf;
+pub mod expr;
+pub mod armasm;
+pub mod arch;
 
 use crossterm::{QueueableCommand, style::{self, Stylize}};
 
 use lexer::Lexer;
-use opcode::OpCode;
-use token::{Token, TokenType};
-use label::Label;
-
-use parser::{
-    parser::InstrParser,
-    brxchg::BrXchgParser,
-    branch::BranchParser,
-    mul::MulParser,
-    mullong::MulLongParser,
-    dataproc::DataProcParser,
-    transfer::{PsrTransferParser, DataTransferParser, BlockTransferParser},
-    swap::DataSwapParser,
-    coproc::{CpOpsParser, CpTransfersParser, CpRegTransParser},
-};
+use token::TokenType;
+use assembler::Assembler;
+use options::{Options, Syntax};
+use elf::ElfWriter;
+use armasm::Armasm;
+use arch::Arch;
 
 fn main() {
     let args: Vec<String> = std::env::args().collect();
 
-    // If the file name is missing
-    if args.len() < 2 {
-        println!("Usage: ./rasm <file>");
-        std::process::exit(1);
+    // Path of the input file and of the optional output file
+    let mut input = None;
+    let mut output = None;
+    let mut options = Options::default();
+
+    let mut args = args.iter().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_ref() {
+            "-o" => output = args.next(),
+            "-c" => options.object = true,
+            "--unified" => options.unified = true,
+            "--strict" => options.strict = true,
+            "--arch" => options.arch = match args.next()
+                .and_then(|arch| arch.parse::<Arch>().ok()) {
+                Some(arch) => arch,
+                None => {
+                    println!("Expected armv4, armv4t, armv5t, armv5te, armv6, \
+                        armv6k, armv6t2 or armv7-a after --arch.");
+                    std::process::exit(1);
+                },
+            },
+            "--syntax" => options.syntax = match args.next()
+                .map(|syntax| syntax.as_str()) {
+                Some("gnu") => Syntax::Gnu,
+                Some("armasm") => Syntax::Armasm,
+                _ => {
+                    println!("Expected gnu or armasm after --syntax.");
+                    std::process::exit(1);
+                },
+            },
+            _ => input = Some(arg),
+        }
     }
 
+    // If the file name is missing
+    let input = match input {
+        Some(input) => input,
+        None => {
+            println!("Usage: ./rasm [-c] [--syntax <gnu|armasm>] [--unified] \
+                [--strict] [--arch <arch>] [-o <output>] <file>");
+            std::process::exit(1);
+        },
+    };
+
     // Read the file
-    let content = match std::fs::read_to_string(&args[1]) {
+    let content = match std::fs::read_to_string(input) {
         Ok(content) => content,
         Err(err) => match err.kind() {
             std::io::ErrorKind::NotFound => {
@@ -53,134 +91,109 @@ fn main() {
         },
     };
 
-    // List of labels
-    let mut labels = Vec::<Label>::new();
+    // Sources in the armasm syntax are translated first, each translated line
+    // coming from a line of the source
+    let mut lines = None;
+    let mut source = None;
+    let content = match options.syntax {
+        Syntax::Gnu => content,
+        Syntax::Armasm => {
+            let mut armasm = Armasm::default();
+            armasm.translate(&content);
+            lines = Some(armasm.lines);
+            source = Some(content.lines().map(str::to_string)
+                .collect::<Vec<_>>());
+            armasm.output
+        },
+    };
+
     // Lex the input
     let mut lexer = Lexer::new(&content);
     lexer.lex();
 
-    if lexer.tokens.len() == 0 {
+    if lexer.tokens.is_empty() {
         panic!("Excepted at least one argument.");
     }
 
-    // Split the tokens after each endline
-    let split: Vec<&[Token]> = lexer.tokens
-        .split(|token| token.token_type == TokenType::Endline)
-        .filter(|tokens| !tokens.is_empty())
-        .collect();
-
-    // First iteration, parsing only labels
-    for tokens in &split {
-        // Ignore instructions
-        if tokens[0].token_type != TokenType::Label {
-            continue;
-        }
+    let mut assembler = Assembler::new(&lexer.tokens, options);
+    assembler.assemble();
 
-        // Save the label
-        labels.push(Label::from(&tokens[0]));
-    }
+    // Line of the source each statement comes from
+    let source_line = |line: usize| match &lines {
+        Some(lines) => lines.get(line).copied().unwrap_or(line),
+        None => line,
+    };
 
-    // Second iteration, parsing instructions
-    for tokens in &split {
-        // Ignore labels
-        if tokens[0].token_type != TokenType::Keyword {
-            continue;
-        }
+    // Calculate the padding needed by the line count
+    let width = source_line(lexer.tokens.last().unwrap().line)
+        .to_string().len();
+
+    // Source line of the previous entry, translated sources being listed
+    // once for all the statements coming from a line
+    let mut previous = None;
+
+    for entry in &assembler.listing {
+        let tokens = entry.tokens;
+        let n = source_line(tokens[0].line);
+
+        // Line count and address
+        let line = format!("{:>width$} | {:08x} | ", n, entry.addr,
+            width=width);
+
+        // Hex format of the output, one word per column
+        let hex = entry.bytes
+            .chunks(4)
+            .map(|chunk| chunk.iter().map(|b| format!("{:02x}", b))
+                .collect::<String>())
+            .map(|word| format!("{:8} ", word))
+            .collect::<String>();
+        // Keep the source aligned for statements producing nothing
+        let hex = format!("{:9}", hex);
+
+        // Instructions and data are told apart by their color
+        let hex = match entry.code {
+            true => hex.green(),
+            false => hex.yellow(),
+        };
 
-        // We know that the first token must be a keyword which contains an
-        // opcode
-        let opcode = InstrParser::parse_opcode(&tokens[0])
-            .expect("Invalid opcode!");
-
-        // Now that we have our opcode, we match it to its parser and store
-        // the result
-        let parsed: u32 = match opcode {
-            OpCode::BX => {
-                // BX{cond} Rn
-                BrXchgParser::parse(opcode, &tokens)
-            },
-            OpCode::B | OpCode::BL => {
-                // B{L}{cond} <expression>
-                BranchParser::parse(opcode, &tokens, &labels)
-            }
-            OpCode::AND | OpCode::EOR | OpCode::SUB | OpCode::RSB |
-            OpCode::ADD | OpCode::ADC | OpCode::SBC | OpCode::RSC |
-            OpCode::TST | OpCode::TEQ | OpCode::CMP | OpCode::CMN |
-            OpCode::ORR | OpCode::MOV | OpCode::BIC | OpCode::MVN => {
-                // * MOV, MVN:
-                //      <opcode>{cond}{S} Rd,<Op2>
-                // * CMP, CMN, TEQ, TST:
-                //      <opcode>{cond} Rn,<Op2>
-                // * AND, EOR, SUB, RSB, ADD, ADC, SBC, RSC, ORR, BIC:
-                //      <opcode>{cond}{S} Rd,Rn,<Op2>
-                DataProcParser::parse(opcode, &tokens)
-            },
-            OpCode::MRS | OpCode::MSR => {
-                // MRS{cond} Rd,<psr>
-                // MSR{cond} <psr>,Rm
-                // MSR{cond} <psrf>,Rm
-                // MSR{cond} <psrf>,<#expression>
-                PsrTransferParser::parse(opcode, &tokens)
-            },
-            OpCode::MUL | OpCode::MLA => {
-                // MUL{cond}{S} Rd,Rm,Rs
-                // MLA{cond}{S} Rd,Rm,Rs,Rn
-                MulParser::parse(opcode, &tokens)
-            },
-            OpCode::UMULL | OpCode::UMLAL | OpCode::SMULL | OpCode::SMLAL => {
-                // UMULL{cond}{S} RdLo,RdHi,Rm,Rs
-                // UMLAL{cond}{S} RdLo,RdHi,Rm,Rs
-                // SMULL{cond}{S} RdLo,RdHi,Rm,Rs
-                // SMLAL{cond}{S} RdLo,RdHi,Rm,Rs
-                MulLongParser::parse(opcode, &tokens)
-            },
-            OpCode::LDR | OpCode::STR => {
-                // <LDR|STR>{cond}{B}{T} Rd,<address>
-                // <LDR|STR>{cond}<H|SH|SB> Rd,<address>
-                DataTransferParser::parse(opcode, &tokens, &labels)
-            },
-            OpCode::LDM | OpCode::STM => {
-                // <LDM|STM>{cond}<FD|ED|FA|EA|IA|IB|DA|DB> Rn{!},<Rlist>{^}
-                BlockTransferParser::parse(opcode, &tokens)
-            },
-            OpCode::SWP => {
-                // <SWP>{cond}{B} Rd,Rm,[Rn]
-                DataSwapParser::parse(opcode, &tokens)
-            },
-            OpCode::CDP => {
-                // CDP{cond} p#,<expression1>,cd,cn,cm{,<expression2>}
-                CpOpsParser::parse(opcode, &tokens)
+        std::io::stdout()
+            .queue(style::PrintStyledContent(line.grey())).unwrap()
+            .queue(style::PrintStyledContent(hex)).unwrap();
+
+        match &source {
+            // Text of the source line instead of its translation
+            Some(source) => if previous != Some(n) {
+                print!(" {}", source.get(n).map_or("", |s| s.trim_end()));
             },
-            OpCode::LDC | OpCode::STC => {
-                // <LDC|STC>{cond}{L} p#,cd,<address>
-                CpTransfersParser::parse(opcode, &tokens, &labels)
+            None => for token in tokens {
+                if token.token_type == TokenType::Comma {
+                    print!("{}", token.value);
+                } else {
+                    print!(" {}", token.value);
+                }
             },
-            OpCode::MRC | OpCode::MCR => {
-                // <MCR|MRC>{cond} p#,<expression1>,Rd,cn,cm{,<expression2>}
-                CpRegTransParser::parse(opcode, tokens)
-            }
-            _ => panic!("Opcode not handled yet."),
-        };
+        }
+        previous = Some(n);
 
-        // Line count
-        let line = format!("{:>width$} | ", tokens[0].line,
-            // Calculate the padding needed
-            width=split.len().to_string().len());
+        // Instruction encoded in place of the one written
+        if let Some(note) = &entry.note {
+            std::io::stdout()
+                .queue(style::PrintStyledContent(format!("  @ {}", note)
+                    .grey())).unwrap();
+        }
+        println!();
+    }
 
-        // Hex format of the output
-        let hex = format!("{:08x} ", parsed.to_be());
+    // Write the object file or the flat image
+    if let Some(output) = output {
+        let content = match assembler.options.object {
+            true => ElfWriter::write(&assembler),
+            false => assembler.image(),
+        };
 
-        std::io::stdout()
-            .queue(style::PrintStyledContent(line.grey())).unwrap()
-            .queue(style::PrintStyledContent(hex.green())).unwrap();
-
-        for token in *tokens {
-            if token.token_type == TokenType::Comma {
-                print!("{}", token.value);
-            } else {
-                print!(" {}", token.value);
-            }
+        if std::fs::write(output, content).is_err() {
+            println!("Unable to write file.");
+            std::process::exit(1);
         }
-        print!("\n");
     }
 }
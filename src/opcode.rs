@@ -1,6 +1,8 @@
 use std::str::FromStr;
 use std::fmt;
 
+use crate::arch::Arch;
+
 /// List of available opcodes
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum OpCode {
@@ -43,6 +45,113 @@ pub enum OpCode {
     UMLAL,
     SMULL,
     SMLAL,
+    // Shifts written as instructions, which are moves of a shifted register
+    LSL,
+    LSR,
+    ASR,
+    ROR,
+    RRX,
+    // Addresses relative to the PC, made of one or two ADD or SUB
+    ADR,
+    ADRL,
+    // ARMv5T
+    BLX,
+    CLZ,
+    BKPT,
+    CDP2,
+    LDC2,
+    STC2,
+    MCR2,
+    MRC2,
+    // ARMv5TE
+    QADD,
+    QSUB,
+    QDADD,
+    QDSUB,
+    SMLA,
+    SMLAW,
+    SMUL,
+    SMULW,
+    PLD,
+    MCRR,
+    MRRC,
+    // ARMv6
+    REV,
+    REV16,
+    REVSH,
+    SXTB,
+    SXTH,
+    SXTB16,
+    UXTB,
+    UXTH,
+    UXTB16,
+    SXTAB,
+    SXTAH,
+    SXTAB16,
+    UXTAB,
+    UXTAH,
+    UXTAB16,
+    SSAT,
+    USAT,
+    SSAT16,
+    USAT16,
+    SEL,
+    PKHBT,
+    PKHTB,
+    USAD8,
+    USADA8,
+    SMUAD,
+    SMUSD,
+    SMLAD,
+    SMLSD,
+    SMLALD,
+    SMLSLD,
+    SMMUL,
+    SMMLA,
+    SMMLS,
+    LDREX,
+    STREX,
+    CPS,
+    CPSIE,
+    CPSID,
+    SETEND,
+    SRS,
+    RFE,
+    // ARMv6K
+    CLREX,
+    YIELD,
+    WFE,
+    WFI,
+    SEV,
+    // ARMv6T2
+    MOVW,
+    MOVT,
+    BFC,
+    BFI,
+    SBFX,
+    UBFX,
+    RBIT,
+    MLS,
+    // ARMv7
+    DMB,
+    DSB,
+    ISB,
+    PLI,
+    DBG,
+    // ARMv6 parallel additions and subtractions, named by their prefix, the
+    // operation following it as a suffix
+    S,
+    Q,
+    SH,
+    U,
+    UQ,
+    UH,
+    // Other pseudo-instructions
+    PUSH,
+    POP,
+    NOP,
+    NEG,
+    CPY,
 }
 
 impl FromStr for OpCode {
@@ -50,45 +159,213 @@ impl FromStr for OpCode {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
-            "adc"   => Ok(Self::ADC),
-            "add"   => Ok(Self::ADD),
-            "and"   => Ok(Self::AND),
-            "b"     => Ok(Self::B),
-            "bic"   => Ok(Self::BIC),
-            "bl"    => Ok(Self::BL),
-            "bx"    => Ok(Self::BX),
-            "cdp"   => Ok(Self::CDP),
-            "cmn"   => Ok(Self::CMN),
-            "cmp"   => Ok(Self::CMP),
-            "eor"   => Ok(Self::EOR),
-            "ldc"   => Ok(Self::LDC),
-            "ldm"   => Ok(Self::LDM),
-            "ldr"   => Ok(Self::LDR),
-            "mcr"   => Ok(Self::MCR),
-            "mla"   => Ok(Self::MLA),
-            "mov"   => Ok(Self::MOV),
-            "mrc"   => Ok(Self::MRC),
-            "mrs"   => Ok(Self::MRS),
-            "msr"   => Ok(Self::MSR),
-            "mul"   => Ok(Self::MUL),
-            "mvn"   => Ok(Self::MVN),
-            "orr"   => Ok(Self::ORR),
-            "rsb"   => Ok(Self::RSB),
-            "rsc"   => Ok(Self::RSC),
-            "sbc"   => Ok(Self::SBC),
-            "stc"   => Ok(Self::STC),
-            "stm"   => Ok(Self::STM),
-            "str"   => Ok(Self::STR),
-            "sub"   => Ok(Self::SUB),
-            "swi"   => Ok(Self::SWI),
-            "swp"   => Ok(Self::SWP),
-            "teq"   => Ok(Self::TEQ),
-            "tst"   => Ok(Self::TST),
-            "umull" => Ok(Self::UMULL),
-            "umlal" => Ok(Self::UMLAL),
-            "smull" => Ok(Self::SMULL),
-            "smlal" => Ok(Self::SMLAL),
-            _       => Err(()),
+            "adc"     => Ok(Self::ADC),
+            "add"     => Ok(Self::ADD),
+            "and"     => Ok(Self::AND),
+            "b"       => Ok(Self::B),
+            "bic"     => Ok(Self::BIC),
+            "bl"      => Ok(Self::BL),
+            "bx"      => Ok(Self::BX),
+            "cdp"     => Ok(Self::CDP),
+            "cmn"     => Ok(Self::CMN),
+            "cmp"     => Ok(Self::CMP),
+            "eor"     => Ok(Self::EOR),
+            "ldc"     => Ok(Self::LDC),
+            "ldm"     => Ok(Self::LDM),
+            "ldr"     => Ok(Self::LDR),
+            "mcr"     => Ok(Self::MCR),
+            "mla"     => Ok(Self::MLA),
+            "mov"     => Ok(Self::MOV),
+            "mrc"     => Ok(Self::MRC),
+            "mrs"     => Ok(Self::MRS),
+            "msr"     => Ok(Self::MSR),
+            "mul"     => Ok(Self::MUL),
+            "mvn"     => Ok(Self::MVN),
+            "orr"     => Ok(Self::ORR),
+            "rsb"     => Ok(Self::RSB),
+            "rsc"     => Ok(Self::RSC),
+            "sbc"     => Ok(Self::SBC),
+            "stc"     => Ok(Self::STC),
+            "stm"     => Ok(Self::STM),
+            "str"     => Ok(Self::STR),
+            "sub"     => Ok(Self::SUB),
+            "swi"     => Ok(Self::SWI),
+            "svc"     => Ok(Self::SWI),
+            "swp"     => Ok(Self::SWP),
+            "teq"     => Ok(Self::TEQ),
+            "tst"     => Ok(Self::TST),
+            "umull"   => Ok(Self::UMULL),
+            "umlal"   => Ok(Self::UMLAL),
+            "smull"   => Ok(Self::SMULL),
+            "smlal"   => Ok(Self::SMLAL),
+            "lsl"     => Ok(Self::LSL),
+            "lsr"     => Ok(Self::LSR),
+            "asr"     => Ok(Self::ASR),
+            "ror"     => Ok(Self::ROR),
+            "rrx"     => Ok(Self::RRX),
+            "adr"     => Ok(Self::ADR),
+            "adrl"    => Ok(Self::ADRL),
+            "blx"     => Ok(Self::BLX),
+            "clz"     => Ok(Self::CLZ),
+            "bkpt"    => Ok(Self::BKPT),
+            "cdp2"    => Ok(Self::CDP2),
+            "ldc2"    => Ok(Self::LDC2),
+            "stc2"    => Ok(Self::STC2),
+            "mcr2"    => Ok(Self::MCR2),
+            "mrc2"    => Ok(Self::MRC2),
+            "qadd"    => Ok(Self::QADD),
+            "qsub"    => Ok(Self::QSUB),
+            "qdadd"   => Ok(Self::QDADD),
+            "qdsub"   => Ok(Self::QDSUB),
+            "smla"    => Ok(Self::SMLA),
+            "smlaw"   => Ok(Self::SMLAW),
+            "smul"    => Ok(Self::SMUL),
+            "smulw"   => Ok(Self::SMULW),
+            "pld"     => Ok(Self::PLD),
+            "mcrr"    => Ok(Self::MCRR),
+            "mrrc"    => Ok(Self::MRRC),
+            "rev"     => Ok(Self::REV),
+            "rev16"   => Ok(Self::REV16),
+            "revsh"   => Ok(Self::REVSH),
+            "sxtb"    => Ok(Self::SXTB),
+            "sxth"    => Ok(Self::SXTH),
+            "sxtb16"  => Ok(Self::SXTB16),
+            "uxtb"    => Ok(Self::UXTB),
+            "uxth"    => Ok(Self::UXTH),
+            "uxtb16"  => Ok(Self::UXTB16),
+            "sxtab"   => Ok(Self::SXTAB),
+            "sxtah"   => Ok(Self::SXTAH),
+            "sxtab16" => Ok(Self::SXTAB16),
+            "uxtab"   => Ok(Self::UXTAB),
+            "uxtah"   => Ok(Self::UXTAH),
+            "uxtab16" => Ok(Self::UXTAB16),
+            "ssat"    => Ok(Self::SSAT),
+            "usat"    => Ok(Self::USAT),
+            "ssat16"  => Ok(Self::SSAT16),
+            "usat16"  => Ok(Self::USAT16),
+            "sel"     => Ok(Self::SEL),
+            "pkhbt"   => Ok(Self::PKHBT),
+            "pkhtb"   => Ok(Self::PKHTB),
+            "usad8"   => Ok(Self::USAD8),
+            "usada8"  => Ok(Self::USADA8),
+            "smuad"   => Ok(Self::SMUAD),
+            "smusd"   => Ok(Self::SMUSD),
+            "smlad"   => Ok(Self::SMLAD),
+            "smlsd"   => Ok(Self::SMLSD),
+            "smlald"  => Ok(Self::SMLALD),
+            "smlsld"  => Ok(Self::SMLSLD),
+            "smmul"   => Ok(Self::SMMUL),
+            "smmla"   => Ok(Self::SMMLA),
+            "smmls"   => Ok(Self::SMMLS),
+            "ldrex"   => Ok(Self::LDREX),
+            "strex"   => Ok(Self::STREX),
+            "cps"     => Ok(Self::CPS),
+            "cpsie"   => Ok(Self::CPSIE),
+            "cpsid"   => Ok(Self::CPSID),
+            "setend"  => Ok(Self::SETEND),
+            "srs"     => Ok(Self::SRS),
+            "rfe"     => Ok(Self::RFE),
+            "clrex"   => Ok(Self::CLREX),
+            "yield"   => Ok(Self::YIELD),
+            "wfe"     => Ok(Self::WFE),
+            "wfi"     => Ok(Self::WFI),
+            "sev"     => Ok(Self::SEV),
+            "movw"    => Ok(Self::MOVW),
+            "movt"    => Ok(Self::MOVT),
+            "bfc"     => Ok(Self::BFC),
+            "bfi"     => Ok(Self::BFI),
+            "sbfx"    => Ok(Self::SBFX),
+            "ubfx"    => Ok(Self::UBFX),
+            "rbit"    => Ok(Self::RBIT),
+            "mls"     => Ok(Self::MLS),
+            "dmb"     => Ok(Self::DMB),
+            "dsb"     => Ok(Self::DSB),
+            "isb"     => Ok(Self::ISB),
+            "pli"     => Ok(Self::PLI),
+            "dbg"     => Ok(Self::DBG),
+            "s"       => Ok(Self::S),
+            "q"       => Ok(Self::Q),
+            "sh"      => Ok(Self::SH),
+            "u"       => Ok(Self::U),
+            "uq"      => Ok(Self::UQ),
+            "uh"      => Ok(Self::UH),
+            "push"    => Ok(Self::PUSH),
+            "pop"     => Ok(Self::POP),
+            "nop"     => Ok(Self::NOP),
+            "neg"     => Ok(Self::NEG),
+            "cpy"     => Ok(Self::CPY),
+            _         => Err(()),
+        }
+    }
+}
+
+impl OpCode {
+    /// Return the suffixes which may complete the opcode in a mnemonic,
+    /// besides the condition code
+    pub fn suffixes(self) -> &'static [&'static str] {
+        match self {
+            Self::AND | Self::EOR | Self::SUB | Self::RSB | Self::ADD |
+            Self::ADC | Self::SBC | Self::RSC | Self::ORR | Self::MOV |
+            Self::BIC | Self::MVN | Self::MUL | Self::MLA | Self::UMULL |
+            Self::UMLAL | Self::SMULL | Self::LSL | Self::LSR | Self::ASR |
+            Self::ROR | Self::RRX | Self::NEG => &["", "s"],
+            // The halfwords of Rm and Rs, bottom or top
+            Self::SMLAL => &["", "s", "bb", "bt", "tb", "tt"],
+            Self::SMLA | Self::SMUL => &["bb", "bt", "tb", "tt"],
+            // The halfword of Rs
+            Self::SMLAW | Self::SMULW => &["b", "t"],
+            // Exchange the halfwords of Rs
+            Self::SMUAD | Self::SMUSD | Self::SMLAD | Self::SMLSD |
+            Self::SMLALD | Self::SMLSLD => &["", "x"],
+            // Round the result
+            Self::SMMUL | Self::SMMLA | Self::SMMLS => &["", "r"],
+            // Operations, along with their names before the unified syntax
+            Self::S | Self::Q | Self::SH | Self::U | Self::UQ | Self::UH => &[
+                "add16", "asx", "sax", "sub16", "add8", "sub8", "addsubx",
+                "subaddx"],
+            Self::LDR => &["", "b", "t", "bt", "h", "sb", "sh", "d"],
+            Self::STR => &["", "b", "t", "bt", "h", "d"],
+            Self::LDM | Self::STM | Self::SRS | Self::RFE => &["ia", "ib", "da",
+                "db", "fd", "ed", "fa", "ea"],
+            Self::LDREX | Self::STREX => &["", "b", "h", "d"],
+            Self::SWP => &["", "b"],
+            Self::LDC | Self::STC | Self::LDC2 | Self::STC2 => &["", "l"],
+            _ => &[""],
+        }
+    }
+
+    /// Return the architecture level which introduced the opcode followed by
+    /// `suffix`
+    pub fn arch(self, suffix: &str) -> Arch {
+        match (self, suffix) {
+            (Self::BX, _) => Arch::V4T,
+            (Self::BLX | Self::CLZ | Self::BKPT | Self::CDP2 | Self::LDC2 |
+                Self::STC2 | Self::MCR2 | Self::MRC2, _) => Arch::V5T,
+            (Self::LDR | Self::STR, "d") |
+            (Self::SMLAL, "bb" | "bt" | "tb" | "tt") |
+            (Self::QADD | Self::QSUB | Self::QDADD | Self::QDSUB | Self::SMLA |
+                Self::SMLAW | Self::SMUL | Self::SMULW | Self::PLD |
+                Self::MCRR | Self::MRRC, _) => Arch::V5TE,
+            (Self::LDREX | Self::STREX, "b" | "h" | "d") |
+            (Self::CLREX | Self::YIELD | Self::WFE | Self::WFI | Self::SEV, _)
+                => Arch::V6K,
+            (Self::REV | Self::REV16 | Self::REVSH | Self::SXTB | Self::SXTH |
+                Self::SXTB16 | Self::UXTB | Self::UXTH | Self::UXTB16 |
+                Self::SXTAB | Self::SXTAH | Self::SXTAB16 | Self::UXTAB |
+                Self::UXTAH | Self::UXTAB16 | Self::SSAT | Self::USAT |
+                Self::SSAT16 | Self::USAT16 | Self::SEL | Self::PKHBT |
+                Self::PKHTB | Self::USAD8 | Self::USADA8 | Self::SMUAD |
+                Self::SMUSD | Self::SMLAD | Self::SMLSD | Self::SMLALD |
+                Self::SMLSLD | Self::SMMUL | Self::SMMLA | Self::SMMLS |
+                Self::LDREX | Self::STREX | Self::CPS | Self::CPSIE |
+                Self::CPSID | Self::SETEND | Self::SRS | Self::RFE | Self::S |
+                Self::Q | Self::SH | Self::U | Self::UQ | Self::UH, _)
+                => Arch::V6,
+            (Self::MOVW | Self::MOVT | Self::BFC | Self::BFI | Self::SBFX |
+                Self::UBFX | Self::RBIT | Self::MLS, _) => Arch::V6T2,
+            (Self::DMB | Self::DSB | Self::ISB | Self::PLI | Self::DBG, _)
+                => Arch::V7A,
+            _ => Arch::V4,
         }
     }
 }
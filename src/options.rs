@@ -0,0 +1,26 @@
+use crate::arch::Arch;
+
+/// Syntax of the source file
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum Syntax {
+    /// Syntax of the GNU assembler
+    #[default]
+    Gnu,
+    /// Syntax of the ARM toolchain (armasm)
+    Armasm,
+}
+
+/// Options given on the command line
+#[derive(Debug, Default)]
+pub struct Options {
+    /// Produce an ELF relocatable object instead of a flat image
+    pub object: bool,
+    pub syntax: Syntax,
+    /// Read the mnemonics in the unified syntax until a `.syntax` directive
+    pub unified: bool,
+    /// Report the immediates which cannot be encoded, instead of encoding
+    /// them with the opposite instruction
+    pub strict: bool,
+    /// Architecture level, whose later instructions are reported
+    pub arch: Arch,
+}
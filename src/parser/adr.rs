@@ -0,0 +1,59 @@
+use crate::opcode::OpCode;
+use crate::token::{Token, TokenType};
+use crate::label::Label;
+use crate::immediate::Immediate;
+use crate::parser::parser::InstrParser;
+use crate::{expect_token, get_reg, get_addr};
+
+pub struct AdrParser;
+
+impl AdrParser {
+    /// Return the binary representation of the instructions making the
+    /// "ADR" and "ADRL" pseudo-instructions, which add the offset of the
+    /// address to the PC, or subtract it when the address is behind
+    #[allow(clippy::identity_op)]
+    pub fn parse(opcode: OpCode, tokens: &[Token], labels: &[Label], addr: u32)
+        -> Vec<u32> {
+        // Condition code
+        let cond = InstrParser::parse_cond(opcode, &tokens[0]) as u32;
+
+        let rd = get_reg!(tokens, 1);
+        expect_token!(tokens, 2, TokenType::Comma);
+
+        let target = get_addr!(tokens, 3, labels);
+        let pc_offset = InstrParser::pc_offset(target, addr);
+        // Decimal representation of the ADD or SUB opcode
+        let opcode_n = if pc_offset < 0 { 2 } else { 4 };
+        let offset = pc_offset.unsigned_abs();
+
+        // Rd = PC +/- imm
+        let encode = |rn: u32, imm: Immediate| {
+            (((((cond << 3 | 0b001) << 4 | opcode_n) << 1 | 0) << 4 | rn)
+                << 4 | rd) << 12 | imm.rotate << 8 | imm.value
+        };
+
+        if opcode == OpCode::ADR {
+            let imm = InstrParser::encode_imm(offset).unwrap_or_else(||
+                panic!("Offset {} cannot be encoded by ADR, use ADRL.",
+                    pc_offset));
+            return vec![encode(15, imm)];
+        }
+
+        // ADRL splits the offset in two immediates, the first one holding
+        // the 8 lowest bits set from an even position, which can always be
+        // encoded. The second instruction is a NOP (MOV R0,R0) when a single
+        // immediate is enough.
+        let shift = offset.trailing_zeros().min(30) & !1;
+        let low = offset & 0xff << shift;
+        let high = offset - low;
+
+        let first = encode(15, InstrParser::encode_imm(low).unwrap());
+        let second = match high {
+            0 => (cond << 28) | 0x01a00000,
+            _ => encode(rd, InstrParser::encode_imm(high).unwrap_or_else(||
+                panic!("Offset {} cannot be encoded by ADRL.", pc_offset))),
+        };
+
+        vec![first, second]
+    }
+}
@@ -0,0 +1,55 @@
+use crate::opcode::OpCode;
+use crate::token::{Token, TokenType};
+use crate::parser::parser::InstrParser;
+use crate::{get_reg, get_value, expect_token};
+
+pub struct BitfieldParser;
+
+impl BitfieldParser {
+    /// Return the binary representation of the "Bit Field" instructions BFC
+    /// and BFI, which clear or insert a bitfield, and SBFX and UBFX, which
+    /// extract one with a sign or zero extension
+    pub fn parse(opcode: OpCode, tokens: &[Token]) -> u32 {
+        // Condition code
+        let cond = InstrParser::parse_cond(opcode, &tokens[0]) as u32;
+
+        let rd = get_reg!(tokens, 1);
+        expect_token!(tokens, 2, TokenType::Comma);
+
+        // Source register, R15 standing for the zeros cleared by BFC
+        let mut rn = 0b1111;
+        let mut idx = 3;
+        if opcode != OpCode::BFC {
+            rn = get_reg!(tokens, 3);
+            expect_token!(tokens, 4, TokenType::Comma);
+            idx = 5;
+        }
+
+        // Least significant bit and width of the bitfield, which must fit in
+        // the register
+        let lsb = get_value!(tokens, idx, 5);
+        expect_token!(tokens, idx + 1, TokenType::Comma);
+        let width = tokens.get(idx + 2)
+            .and_then(InstrParser::parse_number)
+            .expect("Expected an immediate.");
+        if width == 0 || width > 32 - lsb {
+            panic!("Width {} out of range for a bitfield starting at bit {}.",
+                width as i32, lsb);
+        }
+
+        if opcode == OpCode::BFC || opcode == OpCode::BFI {
+            // The bitfield is given by its most significant bit
+            let msb = lsb + width - 1;
+
+            return (((((cond << 7 | 0b0111110) << 5 | msb) << 4 | rd) << 5
+                | lsb) << 3 | 0b001) << 4 | rn;
+        }
+
+        let unsigned = (opcode == OpCode::UBFX) as u32;
+        // The width is encoded minus one
+        let widthm1 = width - 1;
+
+        (((((((cond << 5 | 0b01111) << 1 | unsigned) << 1 | 0b1) << 5
+            | widthm1) << 4 | rd) << 5 | lsb) << 3 | 0b101) << 4 | rn
+    }
+}
@@ -0,0 +1,25 @@
+use crate::opcode::OpCode;
+use crate::cond::Cond;
+use crate::token::{Token, TokenType};
+use crate::parser::parser::InstrParser;
+use crate::get_value;
+
+pub struct BkptParser;
+
+impl BkptParser {
+    /// Return the binary representation of the "Breakpoint" instruction
+    pub fn parse(opcode: OpCode, tokens: &[Token]) -> u32 {
+        if InstrParser::parse_cond(opcode, &tokens[0]) as u32 != Cond::AL as u32 {
+            panic!("BKPT cannot be conditional.");
+        }
+
+        // Comment field, read by the debugger, which is 0 if omitted
+        let imm = match tokens.get(1) {
+            Some(_) => get_value!(tokens, 1, 16),
+            None => 0,
+        };
+
+        ((0b1110_0001_0010 << 12 | imm >> 4) << 4 | 0b0111) << 4
+            | imm & 0xf
+    }
+}
@@ -1,24 +1,52 @@
 use crate::opcode::OpCode;
+use crate::cond::Cond;
 use crate::token::{Token, TokenType};
 use crate::label::Label;
 use crate::parser::parser::InstrParser;
-use crate::get_label;
+use crate::get_addr;
 
 pub struct BranchParser;
 
 impl BranchParser {
     /// Return the binary representation of the "Branch and Branch with Link"
     /// instruction
-    pub fn parse(opcode: OpCode, tokens: &[Token], labels: &Vec<Label>) -> u32 {
+    pub fn parse(opcode: OpCode, tokens: &[Token], labels: &[Label], addr: u32)
+        -> u32 {
         // Condition code
         let cond = InstrParser::parse_cond(opcode, &tokens[0]) as u32;
         // If true, branch with link
-        let link = opcode.to_string().ends_with("l") as u32;
+        let link = (opcode == OpCode::BL) as u32;
 
-        let label = get_label!(tokens, 1, labels);
-        // Calculate offset and take the lower 24 bits
-        let offset = label.offset(&tokens[0], 24);
+        let target = get_addr!(tokens, 1, labels);
+        let pc_offset = InstrParser::pc_offset(target, addr);
+        // The offset is stored in words on 24 bits
+        if pc_offset % 4 != 0 || !(-0x200_0000..0x200_0000).contains(&pc_offset) {
+            panic!("Branch target out of range.");
+        }
+        let offset = (pc_offset >> 2) as u32 & 0xffffff;
 
-        return ((cond << 3 | 0b101) << 1 | link) << 24 | offset;
+        ((cond << 3 | 0b101) << 1 | link) << 24 | offset
+    }
+
+    /// Return the binary representation of the "Branch with Link and
+    /// Exchange" instruction to a label, which switches to Thumb
+    pub fn parse_exchange(opcode: OpCode, tokens: &[Token], labels: &[Label],
+        addr: u32) -> u32 {
+        // The condition field holds the top bits of the opcode
+        if InstrParser::parse_cond(opcode, &tokens[0]) as u32 != Cond::AL as u32 {
+            panic!("BLX to a label cannot be conditional.");
+        }
+
+        let target = get_addr!(tokens, 1, labels);
+        let pc_offset = InstrParser::pc_offset(target, addr);
+        // Thumb instructions are aligned on halfwords, the offset is stored
+        // in words on 24 bits and the H bit holds the halfword
+        if pc_offset % 2 != 0 || !(-0x200_0000..0x200_0000).contains(&pc_offset) {
+            panic!("Branch target out of range.");
+        }
+        let offset = (pc_offset >> 2) as u32 & 0xffffff;
+        let halfword = (pc_offset >> 1) as u32 & 1;
+
+        ((0b1111 << 3 | 0b101) << 1 | halfword) << 24 | offset
     }
 }
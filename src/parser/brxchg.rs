@@ -8,17 +8,24 @@ pub struct BrXchgParser;
 
 impl BrXchgParser {
     /// Return the binary representation of the "Branch and Exchange"
-    /// instruction
+    /// instruction, and of "Branch with Link and Exchange" to a register
     pub fn parse(opcode: OpCode, tokens: &[Token]) -> u32 {
         // Condition code
         let cond = InstrParser::parse_cond(opcode, &tokens[0]) as u32;
+        // If true, branch with link
+        let link = (opcode == OpCode::BLX) as u32;
 
         // Register
         let rn = get_reg!(tokens, 1);
         if rn == Register::CPSR as u32 {
             panic!("Invalid register!");
         }
+        // Branching with link to R15 is UNPREDICTABLE
+        if link == 1 && rn == Register::R15 as u32 {
+            panic!("BLX cannot branch to R15.");
+        }
 
-        return (cond << 24 | 0b0001_0010_1111_1111_1111_0001) << 4 | rn;
+        (cond << 24 | 0b0001_0010_1111_1111_1111_0001 | link << 1) << 4
+            | rn
     }
 }
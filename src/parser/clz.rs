@@ -0,0 +1,22 @@
+use crate::opcode::OpCode;
+use crate::token::{Token, TokenType};
+use crate::parser::parser::InstrParser;
+use crate::{expect_token, get_reg};
+
+pub struct ClzParser;
+
+impl ClzParser {
+    /// Return the binary representation of the "Count Leading Zeros"
+    /// instruction
+    pub fn parse(opcode: OpCode, tokens: &[Token]) -> u32 {
+        // Condition code
+        let cond = InstrParser::parse_cond(opcode, &tokens[0]) as u32;
+
+        let rd = get_reg!(tokens, 1);
+        expect_token!(tokens, 2, TokenType::Comma);
+        let rm = get_reg!(tokens, 3);
+
+        ((cond << 12 | 0b0001_0110_1111) << 4 | rd) << 12
+            | 0b1111_0001 << 4 | rm
+    }
+}
@@ -1,26 +1,45 @@
 use crate::opcode::OpCode;
+use crate::cond::Cond;
 use crate::token::{Token, TokenType};
 use crate::label::Label;
 use crate::parser::parser::InstrParser;
 use crate::{
-    get_cpn, expect_token, get_number, get_creg, get_reg, get_token, get_label
+    get_cpn, expect_token, get_value, get_creg, get_reg, get_token, get_addr
 };
 
+/// Return the condition code of a coprocessor instruction, the ARMv5 forms
+/// ending with 2 being unconditional
+fn parse_cond(opcode: OpCode, token: &Token) -> u32 {
+    let cond = InstrParser::parse_cond(opcode, token) as u32;
+
+    match opcode {
+        OpCode::CDP2 | OpCode::LDC2 | OpCode::STC2 | OpCode::MCR2 |
+        OpCode::MRC2 => {
+            if cond != Cond::AL as u32 {
+                panic!("{} cannot be conditional.", opcode);
+            }
+            0b1111
+        },
+        _ => cond,
+    }
+}
+
 pub struct CpOpsParser;
 
 impl CpOpsParser {
     /// Return the binary representation of the "Coprocessor Data Operations"
     /// instruction
+    #[allow(clippy::identity_op)]
     pub fn parse(opcode: OpCode, tokens: &[Token]) -> u32 {
         // Condition code
-        let cond = InstrParser::parse_cond(opcode, &tokens[0]) as u32;
+        let cond = parse_cond(opcode, &tokens[0]);
 
         // Coprocessor number
         let cpn = get_cpn!(tokens, 1);
         expect_token!(tokens, 2, TokenType::Comma);
 
         // Coprocessor operation code
-        let cpopc = get_number!(tokens, 3).value;
+        let cpopc = get_value!(tokens, 3, 4);
         expect_token!(tokens, 4, TokenType::Comma);
 
         // Coprocessor registers
@@ -35,16 +54,11 @@ impl CpOpsParser {
         let mut cp = 0u32;
         if tokens.get(10).is_some() {
             expect_token!(tokens, 10, TokenType::Comma);
-            cp = get_number!(tokens, 11).value;
-
-            // Larger than 3 bits
-            if cp > 7 {
-                panic!("Immediate value out of range.");
-            }
+            cp = get_value!(tokens, 11, 3);
         }
 
-        return (((((((cond << 4 | 0b1110) << 4 | cpopc) << 4 | crn) << 4 | crd)
-            << 4 | cpn) << 3 | cp) << 1 | 0b0) << 4 | crm;
+        (((((((cond << 4 | 0b1110) << 4 | cpopc) << 4 | crn) << 4 | crd)
+            << 4 | cpn) << 3 | cp) << 1 | 0b0) << 4 | crm
     }
 }
 
@@ -53,9 +67,10 @@ pub struct CpTransfersParser;
 impl CpTransfersParser {
     /// Return the binary representation of the "Coprocessor Data Transfers"
     /// instruction
-    pub fn parse(opcode: OpCode, tokens: &[Token], labels: &Vec<Label>) -> u32 {
+    pub fn parse(opcode: OpCode, tokens: &[Token], labels: &[Label], addr: u32)
+        -> u32 {
         // Condition code
-        let cond = InstrParser::parse_cond(opcode, &tokens[0]) as u32;
+        let cond = parse_cond(opcode, &tokens[0]);
 
         // If true, add offset before transfer, otherwise add offset after
         // transfer
@@ -63,13 +78,11 @@ impl CpTransfersParser {
          // If true, add offset to base, otherwise substract offset from base
         let mut up = true as u32;
         // If true, perform long transfer, otherwise perform short transfer
-        let trans_len = tokens.get(0).unwrap().value
-            // Remove cond to avoid any false positive
-            .replace(&cond.to_string(), "")
-            .ends_with("l") as u32;
-        let write = tokens.last().unwrap().value.eq("!") as u32;
+        let trans_len = (InstrParser::parse_suffix(opcode, &tokens[0]) == "l")
+            as u32;
+        let mut write = tokens.last().unwrap().value.eq("!") as u32;
         // If true, load from memory, otherwise store to memory
-        let load = (opcode == OpCode::LDC) as u32;
+        let load = (opcode == OpCode::LDC || opcode == OpCode::LDC2) as u32;
 
         // Coprocessor number
         let cpn = get_cpn!(tokens, 1);
@@ -83,55 +96,83 @@ impl CpTransfersParser {
         //    1 - <expression>
         //
         //  2.1 - [Rn]
-        //    2 - [Rn,<#expression>]{!}
+        //    2 - [Rn,<#{+/-}expression>]{!}
+        //
+        //    3 - [Rn],<#{+/-}expression>
         //
-        //    3 - [Rn],<#expression>
+        //    4 - [Rn],{<option>}
         let mut offset = 0u32;
         let rn;
 
+        // The offset is stored in words on 8 bits, along with its sign
+        // Offsets written with a minus sign, even -0, are subtracted from
+        // the base
+        let parse_offset = |idx: usize| {
+            let token = tokens.get(idx)
+                .filter(|token| token.token_type == TokenType::Number)
+                .expect("Expected an immediate.");
+            let value = token.number().unwrap_or_else(|err| panic!("{}", err));
+            if value % 4 != 0 || value.unsigned_abs() > 0x3fc {
+                panic!("Coprocessor offset {} out of range.", value);
+            }
+            (!token.is_negative() as u32, value.unsigned_abs() as u32 / 4)
+        };
+
         let token = get_token!(tokens, 5, [TokenType::Keyword,
-            TokenType::OpenBracket]);
-        if token.token_type == TokenType::Keyword {
+            TokenType::Directive, TokenType::Number, TokenType::OpenBracket]);
+        if token.token_type != TokenType::OpenBracket {
             // Case 1
-            let label = get_label!(tokens, 5, labels);
-            // Calculate the offset and take the lower 8 bits
-            offset = label.offset(&tokens[0], 8);
+            let target = get_addr!(tokens, 5, labels);
+            // The offset is stored in words on 8 bits
+            let pc_offset = InstrParser::pc_offset(target, addr);
+            if pc_offset % 4 != 0 || pc_offset.unsigned_abs() > 0x3fc {
+                panic!("Address out of range.");
+            }
+            offset = pc_offset.unsigned_abs() / 4;
             pre = true as u32;
-            up = false as u32;
+            up = (pc_offset >= 0) as u32;
             rn = 15;
         } else {
-            // Case 2.1, 2.2 and 3
+            // Case 2.*, 3 and 4
             rn = get_reg!(tokens, 6);
 
-            // Check if token 8 exists, if not, token 7 must be a close bracket
-            if !tokens.get(8).is_some() {
+            let token = get_token!(tokens, 7, [TokenType::Comma,
+                TokenType::CloseBracket]);
+            if token.token_type == TokenType::Comma {
+                // Case 2.2
+                pre = true as u32;
+                (up, offset) = parse_offset(8);
+                expect_token!(tokens, 9, TokenType::CloseBracket);
+            } else if tokens.len() == 8 + write as usize {
                 // Case 2.1
                 // Offset is zero
-                expect_token!(tokens, 7, TokenType::CloseBracket);
+                pre = true as u32;
             } else {
-                // Case 2.2 and 3
-                let mut idx = 8;
-                let token = get_token!(tokens, 7, [TokenType::Comma,
-                    TokenType::CloseBracket]);
-                if token.token_type == TokenType::CloseBracket {
-                    // Case 3
-                    expect_token!(tokens, 8, TokenType::Comma);
-                    idx = 9;
-                } else {
-                    pre = true as u32;
+                if write == 1 {
+                    panic!("Writeback is implied by post-indexed addressing.");
                 }
-
-                let imm = get_number!(tokens, idx).value;
-                if imm % 4 != 0 {
-                    panic!("Coprocessor offset out of range.");
+                expect_token!(tokens, 8, TokenType::Comma);
+
+                let token = get_token!(tokens, 9, [TokenType::OpenCurlyBrace,
+                    TokenType::Number]);
+                if token.token_type == TokenType::OpenCurlyBrace {
+                    // Case 4
+                    // The address is Rn, the option is left to the
+                    // coprocessor
+                    offset = get_value!(tokens, 10, 8);
+                    expect_token!(tokens, 11, TokenType::CloseCurlyBrace);
+                } else {
+                    // Case 3
+                    // Post-indexed, which always writes back
+                    (up, offset) = parse_offset(9);
+                    write = true as u32;
                 }
-                offset = imm / 4;
             }
         }
 
-        return (((((((((cond << 3 | 0b110) << 1 | pre) << 1 | up)
+        (((((((((cond << 3 | 0b110) << 1 | pre) << 1 | up)
             << 1 | trans_len) << 1 | write) << 1 | load) << 4 | rn)
-            << 4 | crd) << 4 | cpn) << 8 | offset;
+            << 4 | crd) << 4 | cpn) << 8 | offset
     }
 }
 
@@ -142,17 +183,17 @@ impl CpRegTransParser {
     /// instruction
     pub fn parse(opcode: OpCode, tokens: &[Token]) -> u32 {
         // Condition code
-        let cond = InstrParser::parse_cond(opcode, &tokens[0]) as u32;
+        let cond = parse_cond(opcode, &tokens[0]);
 
         // If true, load from memory, otherwise store to memory
-        let load = (opcode == OpCode::MRC) as u32;
+        let load = (opcode == OpCode::MRC || opcode == OpCode::MRC2) as u32;
 
         // Coprocessor number
         let cpn = get_cpn!(tokens, 1);
         expect_token!(tokens, 2, TokenType::Comma);
 
         // Coprocessor operation code
-        let cpopc = get_number!(tokens, 3).value;
+        let cpopc = get_value!(tokens, 3, 3);
         expect_token!(tokens, 4, TokenType::Comma);
 
         let rd = get_reg!(tokens, 5);
@@ -168,16 +209,41 @@ impl CpRegTransParser {
         let mut cp = 0u32;
         if tokens.get(10).is_some() {
             expect_token!(tokens, 10, TokenType::Comma);
-            cp = get_number!(tokens, 11).value;
-
-            // Larger than 3 bits
-            if cp > 7 {
-                panic!("Immediate value out of range.");
-            }
+            cp = get_value!(tokens, 11, 3);
         }
 
-        return ((((((((cond << 4 | 0b1110) << 3 | cpopc) << 1 | load)
+        ((((((((cond << 4 | 0b1110) << 3 | cpopc) << 1 | load)
             << 4 | crn) << 4 | rd) << 4 | cpn) << 3 | cp) << 1 | 0b1)
-            << 4 | crm;
+            << 4 | crm
+    }
+
+    /// Return the binary representation of the "Coprocessor Double Register
+    /// Transfers" instructions MCRR and MRRC
+    pub fn parse_double(opcode: OpCode, tokens: &[Token]) -> u32 {
+        // Condition code
+        let cond = parse_cond(opcode, &tokens[0]);
+
+        // If true, load from coprocessor, otherwise store to coprocessor
+        let load = (opcode == OpCode::MRRC) as u32;
+
+        // Coprocessor number
+        let cpn = get_cpn!(tokens, 1);
+        expect_token!(tokens, 2, TokenType::Comma);
+
+        // Coprocessor operation code
+        let cpopc = get_value!(tokens, 3, 4);
+        expect_token!(tokens, 4, TokenType::Comma);
+
+        // Registers transferred, Rd holding the low word
+        let rd = get_reg!(tokens, 5);
+        expect_token!(tokens, 6, TokenType::Comma);
+        let rn = get_reg!(tokens, 7);
+        expect_token!(tokens, 8, TokenType::Comma);
+
+        // Coprocessor register
+        let crm = get_creg!(tokens, 9);
+
+        ((((((cond << 7 | 0b1100010) << 1 | load) << 4 | rn) << 4 | rd)
+            << 4 | cpn) << 4 | cpopc) << 4 | crm
     }
 }
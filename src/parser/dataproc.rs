@@ -1,12 +1,14 @@
 use crate::opcode::OpCode;
 use crate::token::{Token, TokenType};
 use crate::parser::parser::InstrParser;
+use crate::shift::Shift;
 use crate::{expect_token, get_token, get_reg, get_shift_type};
 
 pub struct DataProcParser;
 
 impl DataProcParser {
     /// Return the binary representation of the "Data Processing" instruction
+    #[allow(clippy::identity_op)]
     pub fn parse(opcode: OpCode, tokens: &[Token]) -> u32 {
         // Condition code
         let cond = InstrParser::parse_cond(opcode, &tokens[0]);
@@ -41,12 +43,7 @@ impl DataProcParser {
         // This is implied for CMP, CMN, TEQ and TST
         let set_cond = match opcode {
             OpCode::CMP | OpCode::CMN | OpCode::TEQ | OpCode::TST => true,
-            _ => {
-                tokens[0].value
-                    // Remove cond to avoid any false positive
-                    .replace(&cond.to_string().to_lowercase(), "")
-                    .ends_with("s")
-            }
+            _ => InstrParser::parse_suffix(opcode, &tokens[0]) == "s",
         } as u32;
 
         // Parse the registers
@@ -86,8 +83,8 @@ impl DataProcParser {
             TokenType::Number]);
         if token.token_type == TokenType::Number {
             // <#expression>
-            let imm = InstrParser::parse_imm(token)
-                .expect("Invalid immediate!");
+            let imm = InstrParser::parse_imm(token).unwrap_or_else(||
+                panic!("Immediate {} cannot be encoded.", token.value));
             op2 = imm.rotate << 8 | imm.value;
         } else {
             // Rm{,<shift>}
@@ -101,21 +98,22 @@ impl DataProcParser {
                 expect_token!(tokens, idx + 2, TokenType::Comma);
 
                 let shift_type = get_shift_type!(tokens, idx + 3);
-                let shift_value = get_token!(tokens, idx + 4,
-                    [TokenType::Keyword, TokenType::Number]);
 
                 // Parse the shift value
-                let shift;
-                if shift_value.token_type == TokenType::Number {
-                    // <shiftname> <expression>
-                    let shift_value = InstrParser::parse_imm(shift_value)
-                        .expect("Invalid shift value!").value;
-                    shift = (shift_value << 2 | shift_type) << 1 | 0;
-                } else {
-                     // <shiftname> <register>
-                    let shift_value = get_reg!(tokens, idx + 4);
-                    shift = ((shift_value << 1 | 0) << 2 | shift_type) << 1 | 1;
-                }
+                let shift = match tokens.get(idx + 4) {
+                    Some(token) if token.token_type == TokenType::Keyword => {
+                        // <shiftname> <register>
+                        if shift_type == Shift::RRX {
+                            panic!("RRX takes no shift register.");
+                        }
+                        let shift_value = get_reg!(tokens, idx + 4);
+                        ((shift_value << 1 | 0) << 2 | shift_type as u32) << 1
+                            | 1
+                    },
+                    // <shiftname> <expression>, or RRX
+                    token => InstrParser::encode_shift(shift_type,
+                        token.and_then(InstrParser::parse_number)),
+                };
 
                 op2 = shift << 4 | rm;
             }
@@ -124,7 +122,44 @@ impl DataProcParser {
         // Convert boolean to u32
         let cond = cond as u32;
 
-        return ((((((cond << 2 | 0b00) << 1 | (is_imm as u32)) << 4 | opcode_n)
-            << 1 | set_cond) << 4 | rn) << 4 | rd) << 12 | op2;
+        ((((((cond << 2 | 0b00) << 1 | (is_imm as u32)) << 4 | opcode_n)
+            << 1 | set_cond) << 4 | rn) << 4 | rd) << 12 | op2
+    }
+
+    /// Return the instruction doing the same as `tokens` with the opposite
+    /// opcode, when its immediate operand cannot be encoded but the negation
+    /// or the complement of the immediate can
+    pub fn substitute(opcode: OpCode, tokens: &[Token])
+        -> Option<(OpCode, Vec<Token>)> {
+        let token = tokens.last()?;
+        if token.token_type != TokenType::Number
+            || tokens[tokens.len() - 2].token_type != TokenType::Comma
+            || InstrParser::parse_imm(token).is_some() {
+            return None;
+        }
+
+        let value = InstrParser::parse_number(token)?;
+        let (other, value) = match opcode {
+            OpCode::MOV => (OpCode::MVN, !value),
+            OpCode::MVN => (OpCode::MOV, !value),
+            OpCode::ADD => (OpCode::SUB, value.wrapping_neg()),
+            OpCode::SUB => (OpCode::ADD, value.wrapping_neg()),
+            OpCode::CMP => (OpCode::CMN, value.wrapping_neg()),
+            OpCode::CMN => (OpCode::CMP, value.wrapping_neg()),
+            OpCode::AND => (OpCode::BIC, !value),
+            OpCode::BIC => (OpCode::AND, !value),
+            OpCode::ADC => (OpCode::SBC, !value),
+            OpCode::SBC => (OpCode::ADC, !value),
+            _ => return None,
+        };
+        InstrParser::encode_imm(value)?;
+
+        // Replace the opcode, keeping the condition code and the suffix
+        let mut tokens = tokens.to_vec();
+        tokens[0].value = other.to_string().to_lowercase()
+            + &tokens[0].value[opcode.to_string().len()..];
+        tokens.last_mut().unwrap().value = format!("#{}", value);
+
+        Some((other, tokens))
     }
 }
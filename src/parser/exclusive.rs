@@ -0,0 +1,83 @@
+use crate::opcode::OpCode;
+use crate::cond::Cond;
+use crate::token::{Token, TokenType};
+use crate::parser::parser::InstrParser;
+use crate::{get_reg, expect_token};
+
+pub struct ExclusiveParser;
+
+impl ExclusiveParser {
+    /// Return the binary representation of the "Load and Store Register
+    /// Exclusive" instructions LDREX{B|H|D} and STREX{B|H|D}
+    pub fn parse(opcode: OpCode, tokens: &[Token]) -> u32 {
+        // Condition code
+        let cond = InstrParser::parse_cond(opcode, &tokens[0]) as u32;
+
+        // Size of the transfer, doublewords using two registers
+        let suffix = InstrParser::parse_suffix(opcode, &tokens[0]);
+        let size = match suffix {
+            ""  => 0b00,
+            "d" => 0b01,
+            "b" => 0b10,
+            "h" => 0b11,
+            _   => unreachable!(),
+        };
+        // If true, load from memory, otherwise store to memory
+        let load = (opcode == OpCode::LDREX) as u32;
+
+        // The stores start with the status register Rd, then come Rt and Rt2
+        // for the doublewords
+        let count = (load == 0) as usize + 1 + (suffix == "d") as usize;
+        let mut regs = Vec::new();
+        for i in 0..count {
+            regs.push(get_reg!(tokens, 1 + 2 * i));
+            expect_token!(tokens, 2 + 2 * i, TokenType::Comma);
+        }
+
+        // The address is [Rn]
+        let idx = 1 + 2 * count;
+        expect_token!(tokens, idx, TokenType::OpenBracket);
+        let rn = get_reg!(tokens, idx + 1);
+        expect_token!(tokens, idx + 2, TokenType::CloseBracket);
+
+        let (rd, transferred) = match load {
+            1 => (0b1111, &regs[..]),
+            _ => (regs[0], &regs[1..]),
+        };
+        let rt = transferred[0];
+
+        // Doublewords use an even register and the one following it
+        if suffix == "d" {
+            if rt & 1 != 0 || rt == 14 {
+                panic!("LDREXD and STREXD take an even register other than \
+                    R14.");
+            }
+            if transferred[1] != rt + 1 {
+                panic!("Expected R{} as the second register.", rt + 1);
+            }
+        }
+
+        if load == 0 && (rd == rn || transferred.contains(&rd)) {
+            panic!("The status register must differ from the base and source \
+                registers.");
+        }
+
+        // The loaded register takes the place of Rd
+        let (rd, rm) = match load {
+            1 => (rt, 0b1111),
+            _ => (rd, rt),
+        };
+
+        ((((((cond << 5 | 0b00011) << 2 | size) << 1 | load) << 4 | rn)
+            << 4 | rd) << 8 | 0b1111_1001) << 4 | rm
+    }
+
+    /// Return the binary representation of the "Clear Exclusive" instruction
+    pub fn parse_clear(opcode: OpCode, tokens: &[Token]) -> u32 {
+        if InstrParser::parse_cond(opcode, &tokens[0]) as u32 != Cond::AL as u32 {
+            panic!("CLREX cannot be conditional.");
+        }
+
+        0b1111_0101_0111_1111_1111_0000_0001_1111
+    }
+}
@@ -0,0 +1,66 @@
+use crate::opcode::OpCode;
+use crate::token::{Token, TokenType};
+use crate::parser::parser::InstrParser;
+use crate::shift::Shift;
+use crate::{get_reg, expect_token};
+
+pub struct ExtendParser;
+
+impl ExtendParser {
+    /// Return the binary representation of the "Extend" instructions, which
+    /// sign or zero extend a byte, a halfword or two bytes of a rotated
+    /// register and may add it to another register
+    pub fn parse(opcode: OpCode, tokens: &[Token]) -> u32 {
+        // Condition code
+        let cond = InstrParser::parse_cond(opcode, &tokens[0]) as u32;
+
+        // Operation, the top bit being set for the zero extensions
+        let op = match opcode {
+            OpCode::SXTB16 | OpCode::SXTAB16 => 0b000,
+            OpCode::SXTB   | OpCode::SXTAB   => 0b010,
+            OpCode::SXTH   | OpCode::SXTAH   => 0b011,
+            OpCode::UXTB16 | OpCode::UXTAB16 => 0b100,
+            OpCode::UXTB   | OpCode::UXTAB   => 0b110,
+            OpCode::UXTH   | OpCode::UXTAH   => 0b111,
+            _                                => unreachable!(),
+        };
+        let accumulate = matches!(opcode, OpCode::SXTAB16 | OpCode::SXTAB |
+            OpCode::SXTAH | OpCode::UXTAB16 | OpCode::UXTAB | OpCode::UXTAH);
+
+        let rd = get_reg!(tokens, 1);
+        expect_token!(tokens, 2, TokenType::Comma);
+
+        // Rn is added to the extended value, R15 standing for no register
+        let mut rn = 0b1111;
+        let mut idx = 3;
+        if accumulate {
+            rn = get_reg!(tokens, 3);
+            expect_token!(tokens, 4, TokenType::Comma);
+            idx = 5;
+        }
+        let rm = get_reg!(tokens, idx);
+
+        // Rm is rotated right by 0, 8, 16 or 24 bits: ,ROR #<rotation>
+        let mut rotate = 0;
+        if tokens.get(idx + 1).is_some() {
+            expect_token!(tokens, idx + 1, TokenType::Comma);
+            let shift = InstrParser::parse_shift(tokens.get(idx + 2)
+                .expect("Expected ROR."));
+            if shift != Some(Shift::ROR) {
+                panic!("Expected ROR.");
+            }
+
+            let amount = tokens.get(idx + 3)
+                .and_then(InstrParser::parse_number)
+                .expect("Expected a rotation.");
+            rotate = match amount {
+                0 | 8 | 16 | 24 => amount / 8,
+                _ => panic!("Rotation {} must be 0, 8, 16 or 24.",
+                    amount as i32),
+            };
+        }
+
+        ((((((cond << 5 | 0b01101) << 3 | op) << 4 | rn) << 4 | rd)
+            << 2 | rotate) << 6 | 0b00_0111) << 4 | rm
+    }
+}
@@ -1,3 +1,10 @@
+// The encoders allow `clippy::identity_op`: fields which are zero, such as
+// `<< 2 | 0b00`, are spelled out so that the shifts follow the layout of the
+// instruction.
+
+// Shares the name of its parent, as it holds `InstrParser` whose parsing is
+// used by all the instruction classes
+#[allow(clippy::module_inception)]
 pub mod parser;
 pub mod brxchg;
 pub mod branch;
@@ -7,3 +14,16 @@ pub mod dataproc;
 pub mod transfer;
 pub mod swap;
 pub mod coproc;
+pub mod adr;
+pub mod swi;
+pub mod clz;
+pub mod bkpt;
+pub mod saturate;
+pub mod rev;
+pub mod extend;
+pub mod pack;
+pub mod parallel;
+pub mod exclusive;
+pub mod system;
+pub mod bitfield;
+pub mod movw;
@@ -0,0 +1,27 @@
+use crate::opcode::OpCode;
+use crate::token::{Token, TokenType};
+use crate::parser::parser::InstrParser;
+use crate::{get_reg, get_value, expect_token};
+
+pub struct MovWideParser;
+
+impl MovWideParser {
+    /// Return the binary representation of the "Move Wide" instruction MOVW,
+    /// which sets a register to a 16-bit immediate, and of the "Move Top"
+    /// instruction MOVT, which sets its top halfword
+    #[allow(clippy::identity_op)]
+    pub fn parse(opcode: OpCode, tokens: &[Token]) -> u32 {
+        // Condition code
+        let cond = InstrParser::parse_cond(opcode, &tokens[0]) as u32;
+
+        // If true, the top halfword is written, otherwise the whole register
+        let top = (opcode == OpCode::MOVT) as u32;
+
+        let rd = get_reg!(tokens, 1);
+        expect_token!(tokens, 2, TokenType::Comma);
+        let imm = get_value!(tokens, 3, 16);
+
+        (((((cond << 5 | 0b00110) << 1 | top) << 2 | 0b00) << 4
+            | imm >> 12) << 4 | rd) << 12 | imm & 0xfff
+    }
+}
@@ -8,27 +8,29 @@ pub struct MulParser;
 impl MulParser {
     /// Return the binary representation of the
     /// "Multiply and Multiply-Accumulate" instruction
+    #[allow(clippy::identity_op)]
     pub fn parse(opcode: OpCode, tokens: &[Token]) -> u32 {
         // Condition code
         let cond = InstrParser::parse_cond(opcode, &tokens[0]);
 
-        let accumulate = (opcode == OpCode::MLA) as u32;
+        let accumulate = (opcode == OpCode::MLA || opcode == OpCode::MLS)
+            as u32;
+        // If true, the product is subtracted from the accumulated register
+        let subtract = (opcode == OpCode::MLS) as u32;
         // If true, condition codes wil be altered
-        let set_cond = tokens[0].value
-            // Remove cond to avoid any fake positive
-            .replace(&cond.to_string().to_lowercase(), "")
-            .ends_with("s") as u32;
+        let set_cond = (InstrParser::parse_suffix(opcode, &tokens[0])
+            == "s") as u32;
 
-        // We expect 3 registers for MUL and 4 for MLA
+        // We expect 3 registers for MUL and 4 for MLA and MLS
         let rd = get_reg!(tokens, 1);
         expect_token!(tokens, 2, TokenType::Comma);
         let rm = get_reg!(tokens, 3);
         expect_token!(tokens, 4, TokenType::Comma);
         let rs = get_reg!(tokens, 5);
 
-        // The 4th register is only used by MLA
+        // The 4th register is only used by MLA and MLS
         let mut rn = 0b0000;
-        if opcode == OpCode::MLA {
+        if accumulate == 1 {
             // Make sure we have a comma after Rs
             expect_token!(tokens, 6, TokenType::Comma);
             rn = get_reg!(tokens, 7);
@@ -37,7 +39,89 @@ impl MulParser {
         // Convert boolean to u32
         let cond = cond as u32;
 
-        return (((((((cond << 6 | 0b000000) << 1 | accumulate) << 1 | set_cond)
-            << 4 | rd) << 4 | rn) << 4 | rs) << 4 | 0b1001) << 4 | rm;
+        ((((((((cond << 5 | 0b00000) << 1 | subtract) << 1 | accumulate)
+            << 1 | set_cond) << 4 | rd) << 4 | rn) << 4 | rs) << 4 | 0b1001)
+            << 4 | rm
+    }
+
+    /// Return the binary representation of the "Signed Multiply (Accumulate)
+    /// Halfwords" instructions SMLA<x><y>, SMLAW<y>, SMUL<x><y> and SMULW<y>
+    pub fn parse_halfword(opcode: OpCode, tokens: &[Token]) -> u32 {
+        // Condition code
+        let cond = InstrParser::parse_cond(opcode, &tokens[0]) as u32;
+
+        // Halfwords of Rm (x) and Rs (y), the top one if true. The word forms
+        // only select the halfword of Rs.
+        let (x, y) = match InstrParser::parse_suffix(opcode, &tokens[0])
+            .as_bytes() {
+            [x, y] => ((*x == b't') as u32, (*y == b't') as u32),
+            [y]    => (0, (*y == b't') as u32),
+            _      => unreachable!(),
+        };
+
+        // Operation, SMULW being told apart from SMLAW by the bit of x
+        let (op, x) = match opcode {
+            OpCode::SMLA  => (0b00, x),
+            OpCode::SMLAW => (0b01, 0),
+            OpCode::SMULW => (0b01, 1),
+            OpCode::SMUL  => (0b11, x),
+            _             => unreachable!(),
+        };
+
+        // We expect 3 registers for the multiplies and 4 for the accumulates
+        let rd = get_reg!(tokens, 1);
+        expect_token!(tokens, 2, TokenType::Comma);
+        let rm = get_reg!(tokens, 3);
+        expect_token!(tokens, 4, TokenType::Comma);
+        let rs = get_reg!(tokens, 5);
+
+        let mut rn = 0b0000;
+        if opcode == OpCode::SMLA || opcode == OpCode::SMLAW {
+            expect_token!(tokens, 6, TokenType::Comma);
+            rn = get_reg!(tokens, 7);
+        }
+
+        ((((((((cond << 5 | 0b00010) << 2 | op) << 5 | rd) << 4 | rn)
+            << 4 | rs) << 1 | 0b1) << 1 | y) << 1 | x) << 5 | rm
+    }
+
+    /// Return the binary representation of the "Dual Signed Multiply" and
+    /// "Signed Most Significant Word Multiply" instructions, which may
+    /// accumulate Rn
+    pub fn parse_dual(opcode: OpCode, tokens: &[Token]) -> u32 {
+        // Condition code
+        let cond = InstrParser::parse_cond(opcode, &tokens[0]) as u32;
+
+        // Exchanges the halfwords of Rs for the dual multiplies, and rounds
+        // the result for the most significant word ones
+        let option = !InstrParser::parse_suffix(opcode, &tokens[0]).is_empty()
+            as u32;
+
+        // Operations, the dual multiplies adding or subtracting the products
+        let (op1, op2) = match opcode {
+            OpCode::SMUAD | OpCode::SMLAD => (0b000, 0b00),
+            OpCode::SMUSD | OpCode::SMLSD => (0b000, 0b01),
+            OpCode::SMMUL | OpCode::SMMLA => (0b101, 0b00),
+            OpCode::SMMLS                 => (0b101, 0b11),
+            _                             => unreachable!(),
+        };
+
+        // We expect 3 registers for the multiplies and 4 for the accumulates
+        let rd = get_reg!(tokens, 1);
+        expect_token!(tokens, 2, TokenType::Comma);
+        let rm = get_reg!(tokens, 3);
+        expect_token!(tokens, 4, TokenType::Comma);
+        let rs = get_reg!(tokens, 5);
+
+        // R15 stands for no accumulated register
+        let mut rn = 0b1111;
+        if matches!(opcode, OpCode::SMLAD | OpCode::SMLSD | OpCode::SMMLA |
+            OpCode::SMMLS) {
+            expect_token!(tokens, 6, TokenType::Comma);
+            rn = get_reg!(tokens, 7);
+        }
+
+        (((((((cond << 5 | 0b01110) << 3 | op1) << 4 | rd) << 4 | rn)
+            << 4 | rs) << 2 | op2) << 1 | option) << 5 | 0b1 << 4 | rm
     }
 }
@@ -17,10 +17,8 @@ impl MulLongParser {
         let accumulate = (opcode == OpCode::UMLAL || opcode == OpCode::SMLAL)
             as u32;
         // If true, condition codes wil be altered
-        let set_cond = tokens[0].value
-            // Remove cond to avoid any fake positive
-            .replace(&cond.to_string().to_lowercase(), "")
-            .ends_with("s") as u32;
+        let suffix = InstrParser::parse_suffix(opcode, &tokens[0]);
+        let set_cond = (suffix == "s") as u32;
 
         // We expect 4 registers
         let rdlo = get_reg!(tokens, 1);
@@ -34,8 +32,44 @@ impl MulLongParser {
         // Convert boolean to u32
         let cond = cond as u32;
 
-        return ((((((((cond << 5 | 0b0001) << 1 | is_signed) << 1 | accumulate)
+        // SMLAL<x><y> multiplies the halfwords of Rm (x) and Rs (y), the top
+        // one if 't'
+        if let [x, y] = suffix.as_bytes() {
+            let x = (*x == b't') as u32;
+            let y = (*y == b't') as u32;
+
+            return ((((((((cond << 5 | 0b00010) << 2 | 0b10) << 5 | rdhi)
+                << 4 | rdlo) << 4 | rs) << 1 | 0b1) << 1 | y) << 1 | x) << 5
+                | rm;
+        }
+
+        ((((((((cond << 5 | 0b0001) << 1 | is_signed) << 1 | accumulate)
             << 1 | set_cond) << 4 | rdhi) << 4 | rdlo) << 4 | rs)
-            << 4 | 0b1001) << 4 | rm;
+            << 4 | 0b1001) << 4 | rm
+    }
+
+    /// Return the binary representation of the "Dual Signed Multiply
+    /// Accumulate Long" instructions SMLALD and SMLSLD
+    pub fn parse_dual(opcode: OpCode, tokens: &[Token]) -> u32 {
+        // Condition code
+        let cond = InstrParser::parse_cond(opcode, &tokens[0]) as u32;
+
+        // If true, the halfwords of Rs are exchanged
+        let exchange = (InstrParser::parse_suffix(opcode, &tokens[0]) == "x")
+            as u32;
+        // If true, the products are subtracted, otherwise they are added
+        let subtract = (opcode == OpCode::SMLSLD) as u32;
+
+        // We expect 4 registers
+        let rdlo = get_reg!(tokens, 1);
+        expect_token!(tokens, 2, TokenType::Comma);
+        let rdhi = get_reg!(tokens, 3);
+        expect_token!(tokens, 4, TokenType::Comma);
+        let rm = get_reg!(tokens, 5);
+        expect_token!(tokens, 6, TokenType::Comma);
+        let rs = get_reg!(tokens, 7);
+
+        ((((((cond << 8 | 0b0111_0100) << 4 | rdhi) << 4 | rdlo)
+            << 4 | rs) << 2 | subtract) << 1 | exchange) << 5 | 0b1 << 4 | rm
     }
 }
@@ -0,0 +1,44 @@
+use crate::opcode::OpCode;
+use crate::token::{Token, TokenType};
+use crate::parser::parser::InstrParser;
+use crate::shift::Shift;
+use crate::{get_reg, expect_token};
+
+pub struct PackParser;
+
+impl PackParser {
+    /// Return the binary representation of the "Pack Halfword" instructions
+    /// PKHBT and PKHTB, and of the "Select Bytes" instruction SEL, which
+    /// share their encoding
+    pub fn parse(opcode: OpCode, tokens: &[Token]) -> u32 {
+        // Condition code
+        let cond = InstrParser::parse_cond(opcode, &tokens[0]) as u32;
+
+        let rd = get_reg!(tokens, 1);
+        expect_token!(tokens, 2, TokenType::Comma);
+        let mut rn = get_reg!(tokens, 3);
+        expect_token!(tokens, 4, TokenType::Comma);
+        let mut rm = get_reg!(tokens, 5);
+
+        let operation = match opcode {
+            OpCode::SEL => 0b1111_1011,
+            // The bottom halfword of Rn and the top one of Rm shifted left
+            OpCode::PKHBT => {
+                InstrParser::parse_imm_shift(tokens, 6, &[Shift::ASL]) | 0b01
+            },
+            // The top halfword of Rn and the bottom one of Rm shifted right,
+            // which is PKHBT with the registers swapped when Rm is not shifted
+            OpCode::PKHTB if tokens.get(6).is_none() => {
+                std::mem::swap(&mut rn, &mut rm);
+                0b01
+            },
+            OpCode::PKHTB => {
+                InstrParser::parse_imm_shift(tokens, 6, &[Shift::ASR]) | 0b01
+            },
+            _ => unreachable!(),
+        };
+
+        ((((cond << 8 | 0b0110_1000) << 4 | rn) << 4 | rd) << 8
+            | operation) << 4 | rm
+    }
+}
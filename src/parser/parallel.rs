@@ -0,0 +1,71 @@
+use crate::opcode::OpCode;
+use crate::token::{Token, TokenType};
+use crate::parser::parser::InstrParser;
+use crate::{get_reg, expect_token};
+
+pub struct ParallelParser;
+
+impl ParallelParser {
+    /// Return the binary representation of the "Parallel Add and Subtract"
+    /// instructions, made of a prefix selecting the arithmetic and of the
+    /// operation applied to each halfword or byte
+    pub fn parse(opcode: OpCode, tokens: &[Token]) -> u32 {
+        // Condition code
+        let cond = InstrParser::parse_cond(opcode, &tokens[0]) as u32;
+
+        // Signed or unsigned, and modulo, saturating or halving
+        let prefix = match opcode {
+            OpCode::S  => 0b001,
+            OpCode::Q  => 0b010,
+            OpCode::SH => 0b011,
+            OpCode::U  => 0b101,
+            OpCode::UQ => 0b110,
+            OpCode::UH => 0b111,
+            _          => unreachable!(),
+        };
+
+        // Operation, ASX and SAX exchanging the halfwords of Rm
+        let operation = match InstrParser::parse_suffix(opcode, &tokens[0]) {
+            "add16"           => 0b000,
+            "asx" | "addsubx" => 0b001,
+            "sax" | "subaddx" => 0b010,
+            "sub16"           => 0b011,
+            "add8"            => 0b100,
+            "sub8"            => 0b111,
+            _                 => unreachable!(),
+        };
+
+        let rd = get_reg!(tokens, 1);
+        expect_token!(tokens, 2, TokenType::Comma);
+        let rn = get_reg!(tokens, 3);
+        expect_token!(tokens, 4, TokenType::Comma);
+        let rm = get_reg!(tokens, 5);
+
+        ((((((cond << 5 | 0b01100) << 3 | prefix) << 4 | rn) << 4 | rd)
+            << 4 | 0b1111) << 4 | operation << 1 | 0b1) << 4 | rm
+    }
+
+    /// Return the binary representation of the "Unsigned Sum of Absolute
+    /// Differences" instructions USAD8 and USADA8
+    pub fn parse_sad(opcode: OpCode, tokens: &[Token]) -> u32 {
+        // Condition code
+        let cond = InstrParser::parse_cond(opcode, &tokens[0]) as u32;
+
+        // We expect 3 registers for USAD8 and 4 for USADA8
+        let rd = get_reg!(tokens, 1);
+        expect_token!(tokens, 2, TokenType::Comma);
+        let rm = get_reg!(tokens, 3);
+        expect_token!(tokens, 4, TokenType::Comma);
+        let rs = get_reg!(tokens, 5);
+
+        // The 4th register is accumulated, R15 standing for no register
+        let mut rn = 0b1111;
+        if opcode == OpCode::USADA8 {
+            expect_token!(tokens, 6, TokenType::Comma);
+            rn = get_reg!(tokens, 7);
+        }
+
+        ((((cond << 8 | 0b0111_1000) << 4 | rd) << 4 | rn) << 4 | rs)
+            << 8 | 0b0001 << 4 | rm
+    }
+}
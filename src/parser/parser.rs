@@ -45,7 +45,27 @@ macro_rules! get_number {
     ($tokens:ident, $idx:expr) => {
         match $tokens.get($idx) {
             Some(x) if x.token_type == TokenType::Number => {
-                InstrParser::parse_imm(x).expect("Invalid immediate!")
+                InstrParser::parse_imm(x).unwrap_or_else(||
+                    panic!("Immediate {} cannot be encoded.", x.value))
+            },
+            _ => panic!("Expected an immediate."),
+        }
+    }
+}
+
+/// Parse a number at the specified index and check that it fits in `bits`
+/// bits
+#[macro_export]
+macro_rules! get_value {
+    ($tokens:ident, $idx:expr, $bits:expr) => {
+        match $tokens.get($idx) {
+            Some(x) if x.token_type == TokenType::Number => {
+                let value = InstrParser::parse_number(x)
+                    .expect("Invalid number!");
+                if value as u64 >> $bits != 0 {
+                    panic!("Value {} does not fit in {} bits.", value, $bits);
+                }
+                value
             },
             _ => panic!("Expected an immediate."),
         }
@@ -58,7 +78,7 @@ macro_rules! get_reg {
     ($tokens:ident, $idx:expr) => {
         InstrParser::parse_reg($tokens.get($idx)
             .expect("Expected a register."))
-            .expect("Invalid register!") as u32;
+            .expect("Invalid register!") as u32
     }
 }
 
@@ -68,7 +88,7 @@ macro_rules! get_shift_type {
     ($tokens:ident, $idx:expr) => {
         InstrParser::parse_shift($tokens.get($idx)
             .expect("Expected a shift type."))
-            .expect("Invalid shift type!") as u32;
+            .expect("Invalid shift type!")
     }
 }
 
@@ -78,7 +98,7 @@ macro_rules! get_cpn {
     ($tokens:ident, $idx:expr) => {
         InstrParser::parse_cpn($tokens.get($idx)
             .expect("Expected a coprocessor number."))
-            .expect("Invalid coprocessor number!") as u32;
+            .expect("Invalid coprocessor number!") as u32
     }
 }
 
@@ -88,26 +108,29 @@ macro_rules! get_creg {
     ($tokens:ident, $idx:expr) => {
         InstrParser::parse_creg($tokens.get($idx)
             .expect("Expected a coprocessor register."))
-            .expect("Invalid coprocessor register!") as u32;
+            .expect("Invalid coprocessor register!") as u32
     }
 }
 
-/// Return a label in `labels` with the name of the token at `idx`
+/// Return the address at index `idx`, which is either a number or the name of
+/// a label in `labels`
 #[macro_export]
-macro_rules! get_label {
+macro_rules! get_addr {
     ($tokens:ident, $idx:expr, $labels:ident) => {
         match $tokens.get($idx) {
-            Some(token) => {
-                if token.token_type != TokenType::Keyword {
-                    panic!("Expected a {:?}, got a {:?}", TokenType::Keyword,
-                        token.token_type);
-                } else {
-                    $labels.iter().find(|label| {
-                        label.name == token.value
-                    }).expect(&format!("No label with name {} found.",
+            Some(token) if token.token_type == TokenType::Number => {
+                InstrParser::parse_number(token).expect("Invalid address!")
+            },
+            // Local labels starting with a dot are lexed as directives
+            Some(token) if token.token_type == TokenType::Keyword
+                || token.token_type == TokenType::Directive => {
+                $crate::label::Label::find($labels, &token.value, token.line)
+                    .expect(&format!("No label with name {} found.",
                         token.value))
-                }
+                    .addr
             },
+            Some(token) => panic!("Expected a label, got a {:?}",
+                token.token_type),
             None => panic!("Expected a label."),
         }
     }
@@ -116,50 +139,87 @@ macro_rules! get_label {
 pub struct InstrParser;
 
 impl InstrParser {
-    /// Parse an instruction opcode
-    pub fn parse_opcode(token: &Token) -> Option<OpCode> {
+    /// Decode a mnemonic into its opcode, its condition code and its suffix.
+    /// The condition code comes before the suffix in the divided syntax
+    /// (`addeqs`, `ldmeqia`) and after it in the unified syntax (`addseq`,
+    /// `ldmiaeq`), where `LDM` and `STM` increment after by default, as `SRS`
+    /// and `RFE` always do.
+    pub fn parse_mnemonic(token: &Token, unified: bool)
+        -> Option<(OpCode, Option<Cond>, String)> {
         if token.token_type != TokenType::Keyword {
             panic!("Expected a {:?}, got a {:?}", TokenType::Keyword,
                 token.token_type);
         }
 
-        // Read the token char by char until we found a valid opcode
-        for i in 0..token.value.len() {
-            if i + 2 <= token.value.len() {
-                // Prevent cases where the opcode is parsed too early
-                // Example: Opcode BL being parsed as B
-                if let Ok(opc) = OpCode::from_str(&token.value[0..i + 2]) {
-                    // Opcode found
-                    return Some(opc);
+        let mnemonic = token.value.to_lowercase();
+        let is_valid = |opcode: OpCode, suffix: &str| {
+            opcode.suffixes().contains(&suffix)
+        };
+        let normalize = |opcode: OpCode, suffix: &str| match opcode {
+            OpCode::LDM | OpCode::STM if unified && suffix.is_empty() => {
+                "ia".to_string()
+            },
+            OpCode::SRS | OpCode::RFE if suffix.is_empty() => "ia".to_string(),
+            _ => suffix.to_string(),
+        };
+
+        // Try the longest opcodes first, so that "bls" is read as B with the
+        // condition LS only because BL cannot be followed by 's'
+        for len in (1..=mnemonic.len()).rev() {
+            let opcode = match OpCode::from_str(&mnemonic[..len]) {
+                Ok(opcode) => opcode,
+                Err(_) => continue,
+            };
+            let rest = &mnemonic[len..];
+
+            // Position of the condition code. The ARMv5TE mnemonics of the
+            // divided syntax end with it as well (`smlabbeq`).
+            let end = rest.len().saturating_sub(2);
+            let positions: &[usize] = match unified {
+                true => &[end],
+                false => &[0, end],
+            };
+            for &pos in positions {
+                let cond = match rest.get(pos..pos + 2).map(Cond::from_str) {
+                    Some(Ok(cond)) => cond,
+                    _ => continue,
+                };
+                let suffix = normalize(opcode,
+                    &format!("{}{}", &rest[..pos], &rest[pos + 2..]));
+                if is_valid(opcode, &suffix) {
+                    return Some((opcode, Some(cond), suffix));
                 }
             }
 
-            if let Ok(opc) = OpCode::from_str(&token.value[0..i + 1]) {
-                // Opcode found
-                return Some(opc);
+            let suffix = normalize(opcode, rest);
+            if is_valid(opcode, &suffix) {
+                return Some((opcode, None, suffix));
             }
         }
 
         None
     }
 
-    /// Parse a condition code
+    /// Parse the condition code of a mnemonic in the divided syntax, which
+    /// follows the opcode
     pub fn parse_cond(opcode: OpCode, token: &Token) -> Cond {
-        // Remove opcode
-        let string = token.value.replace(
-            &opcode.to_string().to_lowercase(), "");
-
-        // Read the string char by char until we find a valid two-character
-        // condition mnemonic
-        for i in 0..string.len() {
-            if let Ok(cond) = Cond::from_str(&string[0..i + 1]) {
-                // Cond found
-                return cond;
-            }
-        }
+        let string = &token.value[opcode.to_string().len()..];
 
         // Return AL (Always) if no cond was found
-        Cond::AL
+        string.get(0..2)
+            .and_then(|cond| Cond::from_str(cond).ok())
+            .unwrap_or(Cond::AL)
+    }
+
+    /// Return the suffix of a mnemonic in the divided syntax, which follows
+    /// the opcode and the condition code
+    pub fn parse_suffix(opcode: OpCode, token: &Token) -> &str {
+        let string = &token.value[opcode.to_string().len()..];
+
+        match string.get(0..2).map(Cond::from_str) {
+            Some(Ok(_)) => &string[2..],
+            _ => string,
+        }
     }
 
     /// Parse a register
@@ -169,41 +229,46 @@ impl InstrParser {
                 token.token_type);
         }
 
-        match Register::from_str(&token.value) {
-            Ok(reg) => Some(reg),
-            Err(_) => None,
+        Register::from_str(&token.value).ok()
+    }
+
+    /// Parse a number which must fit in 32 bits, either signed or unsigned.
+    /// Negative numbers are returned in two's complement.
+    pub fn parse_number(token: &Token) -> Option<u32> {
+        if token.token_type != TokenType::Number {
+            return None;
+        }
+
+        let value = token.number().unwrap_or_else(|err| panic!("{}", err));
+        match value {
+            -0x8000_0000..=0xffff_ffff => Some(value as u32),
+            _ => panic!("Value {} does not fit in 32 bits.", value),
         }
     }
 
+    /// Calculate the offset in bytes between `target` and the PC of the
+    /// instruction at address `addr`, which is 8 bytes ahead
+    pub fn pc_offset(target: u32, addr: u32) -> i32 {
+        target.wrapping_sub(addr.wrapping_add(8)) as i32
+    }
+
     /// Parse an immediate
     pub fn parse_imm(token: &Token) -> Option<Immediate> {
-        let s = token.value.replace("#", "");
-
-        let imm: Option<u32> = match s.contains("0x") {
-            // The immediate is in hex format
-            true => u32::from_str_radix(&s.replace("0x", ""), 16).ok(),
-            false => s.parse::<u32>().ok(),
-        };
-
-        if imm.is_some() {
-            let imm: u32 = imm.unwrap();
+        InstrParser::encode_imm(InstrParser::parse_number(token)?)
+    }
 
-            // Encode the immediate
-            let mut encoded = 0u32;
-            for i in 0..16 {
-                let m = imm.rotate_left(i * 2);
-                if m < 256 {
-                    encoded = (i << 8) | m;
-                    break;
-                }
+    /// Encode `imm` as an immediate operand
+    pub fn encode_imm(imm: u32) -> Option<Immediate> {
+        // Encode the immediate as an 8-bit value rotated right by twice a
+        // 4-bit amount
+        for i in 0..16 {
+            let m = imm.rotate_left(i * 2);
+            if m < 256 {
+                return Some(Immediate { value: m, rotate: i });
             }
-
-            return Some(Immediate {
-                value: encoded & 0xff,
-                rotate: (encoded >> 8) & 0xf
-            });
         }
 
+        // The immediate cannot be encoded
         None
     }
 
@@ -214,10 +279,55 @@ impl InstrParser {
                 token.token_type);
         }
 
-        match Shift::from_str(&token.value) {
-            Ok(reg) => Some(reg),
-            Err(_) => None,
+        Shift::from_str(&token.value).ok()
+    }
+
+    /// Encode the shift `shift` by the immediate `amount` as the amount and
+    /// type fields of a register operand, `amount` being None for RRX. LSR
+    /// and ASR encode a shift by 32 as a shift by 0.
+    pub fn encode_shift(shift: Shift, amount: Option<u32>) -> u32 {
+        let (amount, shift_type) = match (shift, amount) {
+            (Shift::RRX, None) => (0, 0b11),
+            (Shift::RRX, Some(_)) => panic!("RRX takes no shift amount."),
+            (_, None) => panic!("Expected a shift amount."),
+            (Shift::ASL, Some(amount @ 0..=31)) => (amount, 0b00),
+            (Shift::LSR, Some(amount @ 1..=32)) => (amount & 31, 0b01),
+            (Shift::ASR, Some(amount @ 1..=32)) => (amount & 31, 0b10),
+            (Shift::ROR, Some(amount @ 1..=31)) => (amount, 0b11),
+            (shift, Some(amount)) => {
+                let range = match shift {
+                    Shift::ASL => "0-31",
+                    Shift::LSR | Shift::ASR => "1-32",
+                    _ => "1-31",
+                };
+                panic!("Shift amount {} out of range, {} takes {}.",
+                    amount as i32, shift, range)
+            },
+        };
+
+        (amount << 2 | shift_type) << 1
+    }
+
+    /// Parse the optional shift by an immediate `,<shiftname> #expression`
+    /// at index `idx`, which ends the instruction, and return it encoded by
+    /// `encode_shift`, LSL #0 if there is none
+    pub fn parse_imm_shift(tokens: &[Token], idx: usize, shifts: &[Shift])
+        -> u32 {
+        if tokens.get(idx).is_none() {
+            return 0;
         }
+        expect_token!(tokens, idx, TokenType::Comma);
+
+        let shift = InstrParser::parse_shift(tokens.get(idx + 1)
+            .expect("Expected a shift type."))
+            .filter(|shift| shifts.contains(shift))
+            .unwrap_or_else(|| panic!("Expected one of {}.", shifts.iter()
+                .map(Shift::to_string).collect::<Vec<_>>().join(", ")));
+        let amount = tokens.get(idx + 2)
+            .and_then(InstrParser::parse_number)
+            .expect("Expected a shift amount.");
+
+        InstrParser::encode_shift(shift, Some(amount))
     }
 
     /// Parse a PSR format
@@ -227,10 +337,7 @@ impl InstrParser {
                 token.token_type);
         }
 
-        match PSRF::from_str(&token.value) {
-            Ok(flag) => Some(flag),
-            Err(_) => None,
-        }
+        PSRF::from_str(&token.value).ok()
     }
 
     /// Parse a coprocessor number
@@ -240,10 +347,7 @@ impl InstrParser {
                 token.token_type);
         }
 
-        match CPN::from_str(&token.value) {
-            Ok(cpn) => Some(cpn),
-            Err(_) => None,
-        }
+        CPN::from_str(&token.value).ok()
     }
 
     /// Parse a coprocessor register
@@ -253,9 +357,6 @@ impl InstrParser {
                 token.token_type);
         }
 
-        match CoRegister::from_str(&token.value) {
-            Ok(creg) => Some(creg),
-            Err(_) => None,
-        }
+        CoRegister::from_str(&token.value).ok()
     }
 }
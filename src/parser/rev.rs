@@ -0,0 +1,33 @@
+use crate::opcode::OpCode;
+use crate::token::{Token, TokenType};
+use crate::parser::parser::InstrParser;
+use crate::{get_reg, expect_token};
+
+pub struct RevParser;
+
+impl RevParser {
+    /// Return the binary representation of the "Reverse" instructions REV,
+    /// REV16, REVSH and RBIT
+    pub fn parse(opcode: OpCode, tokens: &[Token]) -> u32 {
+        // Condition code
+        let cond = InstrParser::parse_cond(opcode, &tokens[0]) as u32;
+
+        // Operation, reversing the bytes of the word, of each halfword or of
+        // the bottom halfword which is then sign extended, or the bits of the
+        // word
+        let (op1, op2) = match opcode {
+            OpCode::REV   => (0b0110_1011, 0b0011),
+            OpCode::REV16 => (0b0110_1011, 0b1011),
+            OpCode::REVSH => (0b0110_1111, 0b1011),
+            OpCode::RBIT  => (0b0110_1111, 0b0011),
+            _             => unreachable!(),
+        };
+
+        let rd = get_reg!(tokens, 1);
+        expect_token!(tokens, 2, TokenType::Comma);
+        let rm = get_reg!(tokens, 3);
+
+        (((((cond << 8 | op1) << 4 | 0b1111) << 4 | rd) << 4 | 0b1111)
+            << 4 | op2) << 4 | rm
+    }
+}
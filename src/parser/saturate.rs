@@ -0,0 +1,82 @@
+use crate::opcode::OpCode;
+use crate::token::{Token, TokenType};
+use crate::parser::parser::InstrParser;
+use crate::shift::Shift;
+use crate::{get_reg, expect_token};
+
+pub struct SatArithParser;
+
+impl SatArithParser {
+    /// Return the binary representation of the "Saturating Add and Subtract"
+    /// instructions QADD, QSUB, QDADD and QDSUB
+    pub fn parse(opcode: OpCode, tokens: &[Token]) -> u32 {
+        // Condition code
+        let cond = InstrParser::parse_cond(opcode, &tokens[0]) as u32;
+
+        // Operation, QDADD and QDSUB doubling Rn before saturating it
+        let op = match opcode {
+            OpCode::QADD  => 0b00,
+            OpCode::QSUB  => 0b01,
+            OpCode::QDADD => 0b10,
+            OpCode::QDSUB => 0b11,
+            _             => unreachable!(),
+        };
+
+        // We expect 3 registers, Rn coming last
+        let rd = get_reg!(tokens, 1);
+        expect_token!(tokens, 2, TokenType::Comma);
+        let rm = get_reg!(tokens, 3);
+        expect_token!(tokens, 4, TokenType::Comma);
+        let rn = get_reg!(tokens, 5);
+
+        (((((cond << 5 | 0b00010) << 2 | op) << 5 | rn) << 4 | rd)
+            << 8 | 0b0000_0101) << 4 | rm
+    }
+}
+
+pub struct SatParser;
+
+impl SatParser {
+    /// Return the binary representation of the "Saturate" instructions SSAT
+    /// and USAT, which saturate a shifted register, and SSAT16 and USAT16,
+    /// which saturate both of its halfwords
+    pub fn parse(opcode: OpCode, tokens: &[Token]) -> u32 {
+        // Condition code
+        let cond = InstrParser::parse_cond(opcode, &tokens[0]) as u32;
+
+        let unsigned = (opcode == OpCode::USAT || opcode == OpCode::USAT16)
+            as u32;
+        let halfwords = opcode == OpCode::SSAT16 || opcode == OpCode::USAT16;
+
+        let rd = get_reg!(tokens, 1);
+        expect_token!(tokens, 2, TokenType::Comma);
+
+        // Bit position to saturate to, from 1 for the signed saturations and
+        // from 0 for the unsigned ones, on 5 bits or 4 for the halfwords
+        let position = tokens.get(3)
+            .and_then(InstrParser::parse_number)
+            .expect("Expected an immediate.");
+        let max = if halfwords { 16 } else { 32 };
+        let sat = match unsigned {
+            0 if (1..=max).contains(&position) => position - 1,
+            1 if position < max => position,
+            _ => panic!("Saturation position {} out of range.",
+                position as i32),
+        };
+        expect_token!(tokens, 4, TokenType::Comma);
+
+        let rn = get_reg!(tokens, 5);
+
+        if halfwords {
+            return ((((((cond << 5 | 0b01101) << 1 | unsigned) << 2 | 0b10)
+                << 4 | sat) << 4 | rd) << 8 | 0b1111_0011) << 4 | rn;
+        }
+
+        // Rn may be shifted left or arithmetically right first
+        let shift = InstrParser::parse_imm_shift(tokens, 6,
+            &[Shift::ASL, Shift::ASR]);
+
+        ((((((cond << 5 | 0b01101) << 1 | unsigned) << 1 | 0b1) << 5
+            | sat) << 4 | rd) << 8 | shift | 0b01) << 4 | rn
+    }
+}
@@ -0,0 +1,20 @@
+use crate::opcode::OpCode;
+use crate::token::{Token, TokenType};
+use crate::parser::parser::InstrParser;
+use crate::get_value;
+
+pub struct SwiParser;
+
+impl SwiParser {
+    /// Return the binary representation of the "Software Interrupt"
+    /// instruction
+    pub fn parse(opcode: OpCode, tokens: &[Token]) -> u32 {
+        // Condition code
+        let cond = InstrParser::parse_cond(opcode, &tokens[0]) as u32;
+
+        // Comment field, ignored by the processor and read by the handler
+        let comment = get_value!(tokens, 1, 24);
+
+        (cond << 4 | 0b1111) << 24 | comment
+    }
+}
@@ -0,0 +1,185 @@
+use crate::opcode::OpCode;
+use crate::cond::Cond;
+use crate::token::{Token, TokenType};
+use crate::parser::parser::InstrParser;
+use crate::{get_reg, get_token, get_value, expect_token};
+
+pub struct SystemParser;
+
+impl SystemParser {
+    /// Panic if the instruction has a condition code
+    fn unconditional(opcode: OpCode, token: &Token) {
+        if InstrParser::parse_cond(opcode, token) as u32 != Cond::AL as u32 {
+            panic!("{} cannot be conditional.", opcode);
+        }
+    }
+
+    /// Return the binary representation of the "Change Processor State"
+    /// instructions CPS, CPSIE and CPSID
+    pub fn parse_cps(opcode: OpCode, tokens: &[Token]) -> u32 {
+        SystemParser::unconditional(opcode, &tokens[0]);
+
+        // Enables or disables the interrupts
+        let imod = match opcode {
+            OpCode::CPS   => 0b00,
+            OpCode::CPSIE => 0b10,
+            OpCode::CPSID => 0b11,
+            _             => unreachable!(),
+        };
+
+        // Interrupts affected, CPS only changing the mode
+        let mut flags = 0u32;
+        let mut idx = 1;
+        if opcode != OpCode::CPS {
+            let token = get_token!(tokens, 1, [TokenType::Keyword]);
+            for flag in token.value.to_lowercase().chars() {
+                let bit = match flag {
+                    'a' => 0b100,
+                    'i' => 0b010,
+                    'f' => 0b001,
+                    _   => panic!("Invalid interrupt flags {}.", token.value),
+                };
+                if flags & bit != 0 {
+                    panic!("Invalid interrupt flags {}.", token.value);
+                }
+                flags |= bit;
+            }
+
+            idx = 3;
+            if tokens.get(2).is_some() {
+                expect_token!(tokens, 2, TokenType::Comma);
+            }
+        }
+
+        // Optional mode, which is required by CPS
+        let mmod = tokens.get(idx).is_some() as u32;
+        if opcode == OpCode::CPS && mmod == 0 {
+            panic!("Expected a mode.");
+        }
+        let mode = match mmod {
+            1 => get_value!(tokens, idx, 5),
+            _ => 0,
+        };
+
+        ((0b1111_0001_0000 << 2 | imod) << 1 | mmod) << 17 | flags << 6
+            | mode
+    }
+
+    /// Return the binary representation of the "Set Endianness" instruction
+    pub fn parse_setend(opcode: OpCode, tokens: &[Token]) -> u32 {
+        SystemParser::unconditional(opcode, &tokens[0]);
+
+        let token = get_token!(tokens, 1, [TokenType::Keyword]);
+        let big_endian = match token.value.to_lowercase().as_ref() {
+            "be" => 1,
+            "le" => 0,
+            _    => panic!("Expected BE or LE."),
+        };
+
+        0b1111_0001_0000_0001 << 16 | big_endian << 9
+    }
+
+    /// Return the binary representation of the "Store Return State" and
+    /// "Return From Exception" instructions SRS and RFE
+    #[allow(clippy::identity_op)]
+    pub fn parse_return(opcode: OpCode, tokens: &[Token]) -> u32 {
+        SystemParser::unconditional(opcode, &tokens[0]);
+
+        // Stores the return state, otherwise loads it
+        let store = opcode == OpCode::SRS;
+
+        // The stack addressing modes depend on the direction of the
+        // transfer, as for LDM and STM
+        let (pre, up) = match (store,
+            InstrParser::parse_suffix(opcode, &tokens[0])) {
+            (_, "ia") | (true, "ea") | (false, "fd") => (0, 1),
+            (_, "ib") | (true, "fa") | (false, "ed") => (1, 1),
+            (_, "da") | (true, "ed") | (false, "fa") => (0, 0),
+            (_, "db") | (true, "fd") | (false, "ea") => (1, 0),
+            _                                        => unreachable!(),
+        };
+        // Writes back the base register if true
+        let write = tokens.iter()
+            .any(|token| token.token_type == TokenType::Exclamation) as u32;
+
+        if store {
+            // SRS{mode} {SP{!},}#mode{!}, the base being the banked SP
+            let mut idx = 1;
+            let token = get_token!(tokens, 1, [TokenType::Keyword,
+                TokenType::Number]);
+            if token.token_type == TokenType::Keyword {
+                if get_reg!(tokens, 1) != 13 {
+                    panic!("Expected SP as the base register.");
+                }
+                idx = 2 + write as usize;
+                expect_token!(tokens, idx, TokenType::Comma);
+                idx += 1;
+            }
+            let mode = get_value!(tokens, idx, 5);
+
+            return ((((((0b1111100 << 1 | pre) << 1 | up) << 1 | 0b1) << 1
+                | write) << 5 | 0b01101) << 16) | 0b0000_0101 << 8 | mode;
+        }
+
+        // RFE{mode} Rn{!}
+        let rn = get_reg!(tokens, 1);
+
+        (((((0b1111100 << 1 | pre) << 1 | up) << 1 | 0b0) << 1 | write)
+            << 1 | 0b1) << 20 | rn << 16 | 0b1010 << 8
+    }
+
+    /// Return the binary representation of the hints YIELD, WFE, WFI, SEV and
+    /// DBG
+    pub fn parse_hint(opcode: OpCode, tokens: &[Token]) -> u32 {
+        // Condition code
+        let cond = InstrParser::parse_cond(opcode, &tokens[0]) as u32;
+
+        let hint = match opcode {
+            OpCode::YIELD => 0b0000_0001,
+            OpCode::WFE   => 0b0000_0010,
+            OpCode::WFI   => 0b0000_0011,
+            OpCode::SEV   => 0b0000_0100,
+            // The option is passed to the debugger
+            OpCode::DBG   => 0b1111_0000 | get_value!(tokens, 1, 4),
+            _             => unreachable!(),
+        };
+
+        (cond << 16 | 0b0011_0010_0000_1111) << 12 | hint
+    }
+
+    /// Return the binary representation of the "Memory Barrier" instructions
+    /// DMB, DSB and ISB
+    pub fn parse_barrier(opcode: OpCode, tokens: &[Token]) -> u32 {
+        SystemParser::unconditional(opcode, &tokens[0]);
+
+        let op = match opcode {
+            OpCode::DSB => 0b0100,
+            OpCode::DMB => 0b0101,
+            OpCode::ISB => 0b0110,
+            _           => unreachable!(),
+        };
+
+        // Shareability domain and accesses ordered by the barrier, the full
+        // system and every access by default
+        let option = match tokens.get(1) {
+            None => 0b1111,
+            Some(token) if token.token_type == TokenType::Number => {
+                get_value!(tokens, 1, 4)
+            },
+            Some(token) => match token.value.to_lowercase().as_ref() {
+                "sy"             => 0b1111,
+                "st"             => 0b1110,
+                "ish"            => 0b1011,
+                "ishst"          => 0b1010,
+                "nsh" | "un"     => 0b0111,
+                "nshst" | "unst" => 0b0110,
+                "osh"            => 0b0011,
+                "oshst"          => 0b0010,
+                _                => panic!("Invalid barrier option {}.",
+                    token.value),
+            },
+        };
+
+        (0b1111_0101_0111_1111_1111_0000 << 4 | op) << 4 | option
+    }
+}
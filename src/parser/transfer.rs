@@ -1,26 +1,26 @@
 use std::collections::HashSet;
 
 use crate::opcode::OpCode;
+use crate::cond::Cond;
 use crate::register::Register;
-use crate::psrf::PSRF;
 use crate::token::{Token, TokenType};
 use crate::label::Label;
 use crate::parser::parser::InstrParser;
 use crate::{
-    expect_token, get_token, get_reg, get_number, get_shift_type, get_label
+    expect_token, get_token, get_reg, get_number, get_shift_type,
+    get_addr
 };
 
 pub struct PsrTransferParser;
 
 impl PsrTransferParser {
     /// Return the binary representation of the "PSR Transfer" instruction
+    #[allow(clippy::identity_op)]
     pub fn parse(opcode: OpCode, tokens: &[Token]) -> u32 {
         // Condition code
         let cond = InstrParser::parse_cond(opcode, &tokens[0]) as u32;
         // Program status register
         let psr;
-        // PSR format
-        let psrf;
 
         if opcode == OpCode::MRS {
             let rd = get_reg!(tokens, 1);
@@ -36,24 +36,9 @@ impl PsrTransferParser {
             panic!("Expected CPSR or SPSR.");
         }
 
-        // Try to parse a register
-        if let Some(reg) = InstrParser::parse_reg(&tokens[1]) {
-            if reg == Register::CPSR || reg == Register::SPSR {
-                expect_token!(tokens, 2, TokenType::Comma);
-
-                psr = (reg == Register::SPSR) as u32;
-                let rm = get_reg!(tokens, 3) as u32;
-
-                return ((((cond << 5 | 0b00010) << 1 | psr)
-                    << 10 | 0b1010011111) << 8 | 0b00000000) << 4 | rm;
-            } else {
-                panic!("Expected CPSR or SPSR.");
-            }
-        } else {
-            // If the parsing didn't success, we know it's a flag
-            psrf = InstrParser::parse_psrf(&tokens[1])
-                .expect("Expected CPSR_flg or SPSR_flg.") as u32;
-        }
+        // The PSR, along with the fields written
+        let psrf = InstrParser::parse_psrf(&tokens[1])
+            .expect("Expected CPSR or SPSR with its fields.");
 
         expect_token!(tokens, 2, TokenType::Comma);
 
@@ -72,10 +57,11 @@ impl PsrTransferParser {
             source_op = 0b00000000 << 4 | rm;
         }
 
-        let pd = (psrf == PSRF::SPSR as u32) as u32;
+        let pd = psrf.spsr as u32;
 
-        return (((((cond << 2 | 0b00) << 1 | (is_imm as u32)) << 2 | 0b10)
-            << 1 | pd) << 10 | 0b1010001111) << 12 | source_op;
+        (((((((cond << 2 | 0b00) << 1 | (is_imm as u32)) << 2 | 0b10)
+            << 1 | pd) << 2 | 0b10) << 4 | psrf.mask) << 4 | 0b1111) << 12
+            | source_op
     }
 }
 
@@ -84,20 +70,19 @@ pub struct DataTransferParser;
 impl DataTransferParser {
     /// Return the binary representation of the "Single Data Transfer"
     /// instruction
-    pub fn parse(opcode: OpCode, tokens: &[Token], labels: &Vec<Label>) -> u32 {
+    #[allow(clippy::identity_op)]
+    pub fn parse(opcode: OpCode, tokens: &[Token], labels: &[Label], addr: u32)
+        -> u32 {
         // Condition code
         let cond = InstrParser::parse_cond(opcode, &tokens[0]) as u32;
 
-        // Remove all characters except the letters 'B' and 'T'
-        let clean_opcode = tokens[0].value
-            .replace(&cond.to_string().to_lowercase(), "")
-            .replace(&opcode.to_string().to_lowercase(), "");
+        // Letters following the opcode and the condition code
+        let clean_opcode = InstrParser::parse_suffix(opcode, &tokens[0]);
 
-        // Check if the transfer is halfword and signed
-        let hw_sgd = match clean_opcode.as_ref() {
-            "h" | "sb" | "sh" => true,
-            _ => false,
-        };
+        // Check if the transfer is halfword and signed, or doubleword, which
+        // share their encoding
+        let dual = clean_opcode == "d";
+        let hw_sgd = matches!(clean_opcode, "h" | "sb" | "sh") || dual;
 
         // True if the offset is an immediate value, false if it's a register
         let mut is_reg = false as u32;
@@ -120,6 +105,22 @@ impl DataTransferParser {
         let rd = get_reg!(tokens, 1);
         expect_token!(tokens, 2, TokenType::Comma);
 
+        // Doubleword transfers use an even register and the one following it,
+        // which may be written as well
+        let mut tokens = tokens.to_vec();
+        if dual {
+            if rd & 1 != 0 || rd == 14 {
+                panic!("LDRD and STRD take an even register other than R14.");
+            }
+            if tokens.get(4).map(|token| token.token_type)
+                == Some(TokenType::Comma) {
+                if get_reg!(tokens, 3) != rd + 1 {
+                    panic!("Expected R{} as the second register.", rd + 1);
+                }
+                tokens.drain(3..5);
+            }
+        }
+
         // Now we parse an <address> which can either be:
         //    1 - <expression>
         //
@@ -138,46 +139,68 @@ impl DataTransferParser {
         let mut offset = 0u32;
 
         let token = get_token!(tokens, 3, [TokenType::OpenBracket,
-            TokenType::Keyword]);
+            TokenType::Keyword, TokenType::Directive, TokenType::Number]);
         if token.token_type == TokenType::OpenBracket {
             // Case 2.* and 3.*
             // The next token after the bracket is always a register
             rn = get_reg!(tokens, 4);
 
-            // Check if there is no more token after token 5
-            if tokens.get(6).is_none() {
-                // Case 2.1
-                // Offset is zero
-                expect_token!(tokens, 5, TokenType::CloseBracket);
+            // Number of tokens after the address, the optional '!'
+            let bang = write as usize;
+
+            // We expect either a comma or a close bracket
+            let token = get_token!(tokens, 5, [TokenType::Comma,
+                TokenType::CloseBracket]);
+            let mut idx = 5;
+            if token.token_type == TokenType::Comma {
+                // Case 2.2 and 2.3
                 pre = true as u32;
+            } else if tokens.len() == 6 + bang {
+                // Case 2.1
+                // Offset is zero. The T forms only exist post-indexed, which
+                // always writes back.
+                if t && write == 1 {
+                    panic!("Writeback is implied by post-indexed addressing.");
+                }
+                pre = !t as u32;
+                write |= t as u32;
+                idx = 0;
             } else {
-                // Case 2.2, 2.3 and 3.*
-                let mut idx = 5;
-
-                // We expect either a comma or a close bracket
-                let token = get_token!(tokens, idx, [TokenType::Comma,
-                    TokenType::CloseBracket]);
-                if token.token_type == TokenType::CloseBracket {
-                    // Post-indexed instruction
-                    expect_token!(tokens, idx + 1, TokenType::Comma);
-                    idx = 6;
-                    // Set W bit if letter 't' is present
-                    write = t as u32;
-                } else {
-                    pre = true as u32;
+                // Post-indexed instruction, which always writes back. The W
+                // bit is then only set for the T forms.
+                if write == 1 {
+                    panic!("Writeback is implied by post-indexed addressing.");
                 }
+                expect_token!(tokens, 6, TokenType::Comma);
+                idx = 6;
+                write = t as u32;
+            }
 
+            if t && pre == 1 {
+                panic!("The T forms only take post-indexed addresses.");
+            }
+
+            if idx != 0 {
                 let token = get_token!(tokens, idx + 1,
                     [TokenType::Keyword, TokenType::Number, TokenType::Plus,
                     TokenType::Minus]);
                 if token.token_type == TokenType::Number {
                     // Case 2.2 and 3.1
-                    offset = get_number!(tokens, idx + 1).value;
+                    // Offsets written with a minus sign, even -0, are
+                    // subtracted from the base
+                    let value = token.number()
+                        .unwrap_or_else(|err| panic!("{}", err));
+                    up = !token.is_negative() as u32;
+
+                    // Halfword and signed transfers only have 8 bits
+                    let bits = if hw_sgd { 8 } else { 12 };
+                    if value.unsigned_abs() >> bits != 0 {
+                        panic!("Offset {} does not fit in {} bits.", value,
+                            bits);
+                    }
+                    offset = value.unsigned_abs() as u32;
                 } else {
                     // Case 2.3 and 3.2
-                    let token = get_token!(tokens, idx + 1,
-                        [TokenType::Plus, TokenType::Minus,
-                        TokenType::Keyword]);
                     if token.token_type != TokenType::Keyword {
                         // Set to sustract offset from base if false
                         up = (token.token_type == TokenType::Plus) as u32;
@@ -186,51 +209,61 @@ impl DataTransferParser {
 
                     rm = get_reg!(tokens, idx + 1);
                     let mut shift = 0u32;
-                    // Check if the last token exists, we add write to idx
-                    // in case we have an exclamation point at the end
-                    if tokens.get(idx + 3 + write as usize).is_some()
-                        && !hw_sgd {
+                    // Check if the shift type exists, we add pre and bang to
+                    // idx to skip the close bracket and the exclamation point
+                    if tokens.get(idx + 3 + pre as usize + bang).is_some() {
+                        if hw_sgd {
+                            panic!("Halfword and signed transfers take no \
+                                shift.");
+                        }
+
                         // The next token must be a comma if the previous token
                         // exists
                         expect_token!(tokens, idx + 2, TokenType::Comma);
 
                         let shift_type = get_shift_type!(tokens, idx + 3);
                         // Can't be a register
-                        let shift_value = get_number!(tokens, idx + 4)
-                            .value;
-                        shift = (shift_value << 2 | shift_type) << 1 | 0;
+                        shift = InstrParser::encode_shift(shift_type,
+                            tokens.get(idx + 4)
+                                .and_then(InstrParser::parse_number));
                     }
 
                     is_reg = true as u32;
                     offset = shift << 4 | rm;
                 }
 
-                if idx == 5 {
+                if pre == 1 {
                     // The instruction must end with a close bracket
-                    expect_token!(tokens, tokens.len() - 1 - write as
-                        usize, TokenType::CloseBracket);
+                    expect_token!(tokens, tokens.len() - 1 - bang,
+                        TokenType::CloseBracket);
                 }
             }
         } else {
             // Case 1
-            let label = get_label!(tokens, 3, labels);
-            // Calculate the offset and take the lower 12 bits
-            offset = label.offset(&tokens[0], 12);
+            let target = get_addr!(tokens, 3, labels);
+            let pc_offset = InstrParser::pc_offset(target, addr);
+            offset = pc_offset.unsigned_abs();
+            if offset > if hw_sgd { 0xff } else { 0xfff } {
+                panic!("Address out of range.");
+            }
             pre = true as u32;
-            up = false as u32;
+            up = (pc_offset >= 0) as u32;
             rn = 15;
         }
 
         if hw_sgd {
-            let sh = match clean_opcode.as_ref() {
+            let sh = match clean_opcode {
                 // Unsigned halfwords
                 "h"  => 0b01,
                 // Signed byte
                 "sb" => 0b10,
                 // Signed halfwords
                 "sh" => 0b11,
+                // Doublewords, which are loaded or stored by SH alone
+                "d"  => if load == 1 { 0b10 } else { 0b11 },
                 _    => unreachable!(),
             };
+            let load = if dual { 0 } else { load };
 
             if is_reg == true as u32 {
                 return (((((((((((cond << 3 | 0b000) << 1 | pre) << 1 | up)
@@ -248,9 +281,41 @@ impl DataTransferParser {
         }
 
 
-        return (((((((((cond << 2 | 0b01) << 1 | is_reg) << 1 | pre) << 1 | up)
+        (((((((((cond << 2 | 0b01) << 1 | is_reg) << 1 | pre) << 1 | up)
             << 1 | byte_trans) << 1 | write) << 1 | load) << 4 | rn)
-            << 4 | rd) << 12 | offset;
+            << 4 | rd) << 12 | offset
+    }
+
+    /// Return the binary representation of the "Preload Data" and "Preload
+    /// Instruction" instructions PLD and PLI, which are encoded as an
+    /// unconditional LDRB to the PC with a pre-indexed address and no
+    /// writeback, PLI clearing the P bit
+    pub fn parse_preload(opcode: OpCode, tokens: &[Token], labels: &[Label],
+        addr: u32) -> u32 {
+        if InstrParser::parse_cond(opcode, &tokens[0]) as u32 != Cond::AL as u32
+        {
+            panic!("{} cannot be conditional.", opcode);
+        }
+
+        // LDRB PC,<address>
+        let mut ldrb = vec![
+            Token { value: "ldrb".to_string(), ..tokens[0].clone() },
+            Token { value: "pc".to_string(), ..tokens[0].clone() },
+            Token { token_type: TokenType::Comma, value: ",".to_string(),
+                ..tokens[0].clone() },
+        ];
+        ldrb.extend_from_slice(&tokens[1..]);
+
+        let parsed = DataTransferParser::parse(OpCode::LDR, &ldrb, labels,
+            addr);
+        // Bits P and W
+        if parsed >> 24 & 1 == 0 || parsed >> 21 & 1 == 1 {
+            panic!("{} only takes pre-indexed addresses without writeback.",
+                opcode);
+        }
+
+        let pre = (opcode == OpCode::PLD) as u32;
+        0b1111 << 28 | parsed & 0x0eff_ffff | pre << 24
     }
 }
 
@@ -264,9 +329,9 @@ impl BlockTransferParser {
         // Condition code
         let cond = InstrParser::parse_cond(opcode, &tokens[0]) as u32;
 
-        // Remove condition code
-        let clean_opcode = tokens[0].value
-            .replace(&cond.to_string().to_lowercase(), "");
+        // Opcode followed by the addressing mode
+        let clean_opcode = opcode.to_string().to_lowercase()
+            + InstrParser::parse_suffix(opcode, &tokens[0]);
 
         // If true, add offset before transfer, otherwise add offset after
         // transfer
@@ -388,7 +453,7 @@ impl BlockTransferParser {
             rlist_bin |= 2u32.pow(reg);
         }
 
-        return (((((((cond << 3 | 0b100) << 1 | pre) << 1 | up) << 1 | force)
-            << 1 | write) << 1 | load) << 4 | rn) << 16 | rlist_bin;
+        (((((((cond << 3 | 0b100) << 1 | pre) << 1 | up) << 1 | force)
+            << 1 | write) << 1 | load) << 4 | rn) << 16 | rlist_bin
     }
 }
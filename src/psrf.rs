@@ -1,19 +1,54 @@
 use std::str::FromStr;
 
-/// PSR formats
-pub enum PSRF {
-    CPSR,
-    SPSR,
+/// PSR fields written by MSR
+pub struct PSRF {
+    /// True for the SPSR, false for the CPSR
+    pub spsr: bool,
+    /// Mask of the fields written, from the highest bit: flags (f), status
+    /// (s), extension (x) and control (c)
+    pub mask: u32,
 }
 
 impl FromStr for PSRF {
     type Err = ();
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "cpsr_flg" => Ok(Self::CPSR),
-            "spsr_flg" => Ok(Self::SPSR),
-            _          => Err(()),
-        }
+        let (psr, fields) = s.split_once('_').unwrap_or((s, ""));
+        let spsr = match psr {
+            // APSR is the view of the CPSR from the user mode
+            "cpsr" | "apsr" => false,
+            "spsr"          => true,
+            _               => return Err(()),
+        };
+
+        let mask = match (psr, fields) {
+            ("apsr", "" | "nzcvq") => 0b1000,
+            ("apsr", "g")          => 0b0100,
+            ("apsr", "nzcvqg")     => 0b1100,
+            ("apsr", _)            => return Err(()),
+            (_, "" | "all")        => 0b1001,
+            (_, "flg")             => 0b1000,
+            (_, "ctl")             => 0b0001,
+            (_, fields)            => {
+                // Any combination of the fields, each given once
+                let mut mask = 0;
+                for field in fields.chars() {
+                    let bit = match field {
+                        'c' => 0b0001,
+                        'x' => 0b0010,
+                        's' => 0b0100,
+                        'f' => 0b1000,
+                        _   => return Err(()),
+                    };
+                    if mask & bit != 0 {
+                        return Err(());
+                    }
+                    mask |= bit;
+                }
+                mask
+            },
+        };
+
+        Ok(Self { spsr, mask })
     }
 }
@@ -45,6 +45,7 @@ impl FromStr for Register {
             "r14" | "lr"        => Ok(Self::R14),
             "r15" | "pc"        => Ok(Self::R15),
             "cpsr" | "cpsr_all" => Ok(Self::CPSR),
+            "apsr"              => Ok(Self::CPSR),
             "spsr" | "spsr_all" => Ok(Self::SPSR),
             _                   => Err(()),
         }
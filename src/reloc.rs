@@ -0,0 +1,28 @@
+/// Relocation types, numbered as in the ELF for the ARM architecture
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RelocType {
+    /// 32-bit absolute address
+    Abs32 = 2,
+    /// Offset of a BL instruction
+    Call = 28,
+    /// Offset of a B instruction
+    Jump24 = 29,
+}
+
+/// Symbol whose address is added to a field by the linker
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RelocTarget {
+    /// Start of the section at this index
+    Section(usize),
+    /// Symbol of the symbol table with this name
+    Symbol(String),
+}
+
+/// Field left for the linker to complete
+#[derive(Debug)]
+pub struct Reloc {
+    /// Offset of the field from the start of its subsection
+    pub offset: u32,
+    pub target: RelocTarget,
+    pub reloc_type: RelocType,
+}
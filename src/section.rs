@@ -0,0 +1,136 @@
+use std::collections::BTreeMap;
+
+use crate::reloc::Reloc;
+
+/// Position of the location counter: a section and one of its subsections
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SectionRef {
+    /// Index of the section
+    pub section: usize,
+    /// Number of the subsection
+    pub subsection: u32,
+}
+
+/// Kind of content found in a section, marked by mapping symbols in object
+/// files so that disassemblers can tell code from data
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mapping {
+    /// ARM instructions ($a)
+    Arm,
+    /// Thumb instructions ($t)
+    Thumb,
+    /// Data ($d)
+    Data,
+}
+
+/// Attributes of a section, given by the flags and the type of `.section`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SectionFlags {
+    /// Occupies memory at run time (a)
+    pub alloc: bool,
+    /// Writable (w)
+    pub write: bool,
+    /// Contains instructions (x)
+    pub exec: bool,
+    /// Takes no space in the file (%nobits)
+    pub nobits: bool,
+}
+
+/// Part of a section with its own location counter and alignment
+pub struct SubSection {
+    /// Bytes emitted so far, its length is the location counter
+    pub data: Vec<u8>,
+    /// Offsets at which the kind of content changes
+    pub mappings: Vec<(u32, Mapping)>,
+    /// Fields completed by the linker
+    pub relocs: Vec<Reloc>,
+    /// Largest alignment (in bytes) requested in the subsection
+    pub align: u32,
+    /// Offset of the subsection from the start of its section, known once
+    /// the layout is done
+    pub offset: u32,
+}
+
+pub struct Section {
+    pub name: String,
+    /// Subsections, concatenated in increasing order of their number
+    pub subsections: BTreeMap<u32, SubSection>,
+    /// Address of the section, known once the layout is done
+    pub addr: u32,
+    /// Attributes given to the section, guessed from its name if none
+    pub flags: Option<SectionFlags>,
+}
+
+impl SubSection {
+    /// Return the kind of the last content emitted
+    pub fn mapping(&self) -> Option<Mapping> {
+        self.mappings.last().map(|&(_, mapping)| mapping)
+    }
+
+    /// Append `bytes` of kind `mapping`
+    pub fn emit(&mut self, bytes: &[u8], mapping: Mapping) {
+        if self.mapping() != Some(mapping) {
+            self.mappings.push((self.data.len() as u32, mapping));
+        }
+        self.data.extend(bytes);
+    }
+}
+
+impl Section {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            subsections: BTreeMap::new(),
+            addr: 0,
+            flags: None,
+        }
+    }
+
+    /// Return the subsection `n`, creating it if needed
+    pub fn subsection(&mut self, n: u32) -> &mut SubSection {
+        self.subsections.entry(n).or_insert(SubSection {
+            data: Vec::new(),
+            mappings: Vec::new(),
+            relocs: Vec::new(),
+            align: 1,
+            offset: 0,
+        })
+    }
+
+    /// Alignment of the section, which is the largest alignment of its
+    /// subsections
+    pub fn align(&self) -> u32 {
+        self.subsections.values().map(|sub| sub.align).max().unwrap_or(1)
+    }
+
+    /// Place the subsections one after the other and return the size of the
+    /// section
+    pub fn layout(&mut self) -> u32 {
+        let mut size = 0u32;
+        for sub in self.subsections.values_mut() {
+            size = size.next_multiple_of(sub.align);
+            sub.offset = size;
+            size += sub.data.len() as u32;
+        }
+        size
+    }
+
+    /// Empty the subsections before a new pass, their offsets are kept
+    pub fn clear(&mut self) {
+        for sub in self.subsections.values_mut() {
+            sub.data.clear();
+            sub.mappings.clear();
+            sub.relocs.clear();
+        }
+    }
+
+    /// Return the content of the section, with its subsections concatenated
+    pub fn data(&self) -> Vec<u8> {
+        let mut data = Vec::new();
+        for sub in self.subsections.values() {
+            data.resize(sub.offset as usize, 0);
+            data.extend(&sub.data);
+        }
+        data
+    }
+}
@@ -1,6 +1,7 @@
+use std::fmt;
 use std::str::FromStr;
 
-#[derive(Debug)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 /// List of shifts applied to immediates and registers
 pub enum Shift {
     /// Logical left
@@ -11,6 +12,8 @@ pub enum Shift {
     ASR,
     /// Rotate right
     ROR,
+    /// Rotate right by one bit through the carry flag, encoded as ROR #0
+    RRX,
 }
 
 impl FromStr for Shift {
@@ -23,7 +26,22 @@ impl FromStr for Shift {
             "lsr"         => Ok(Self::LSR),
             "asr"         => Ok(Self::ASR),
             "ror"         => Ok(Self::ROR),
+            "rrx"         => Ok(Self::RRX),
             _             => Err(()),
         }
     }
 }
+
+impl fmt::Display for Shift {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            // Written LSL in the ARM documentation and in disassemblies
+            Self::ASL => "LSL",
+            Self::LSR => "LSR",
+            Self::ASR => "ASR",
+            Self::ROR => "ROR",
+            Self::RRX => "RRX",
+        };
+        write!(f, "{}", name)
+    }
+}
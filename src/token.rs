@@ -2,8 +2,10 @@
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum TokenType {
     Keyword,
+    Directive,
     Number,
     Label,
+    String,
     Exclamation,
     Plus,
     Minus,
@@ -13,12 +15,144 @@ pub enum TokenType {
     CloseCurlyBrace,
     Comma,
     Caret,
+    Hash,
+    Star,
+    Slash,
+    Percent,
+    Ampersand,
+    Pipe,
+    Tilde,
+    OpenParen,
+    CloseParen,
+    Equal,
+    EqualEqual,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    ShiftLeft,
+    ShiftRight,
+    AndAnd,
+    OrOr,
     Endline,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Token {
     pub token_type: TokenType,
     pub value: String,
     pub line: usize,
 }
+
+impl Token {
+    /// Return the value of a number token, which is either an integer with an
+    /// optional sign and radix prefix, or a character constant:
+    /// {#}{+|-}<0x|0b|0o|0><digits> or {#}'<char>'
+    pub fn number(&self) -> Result<i64, String> {
+        let s = self.value.strip_prefix('#').unwrap_or(&self.value);
+        let (negative, s) = match s.as_bytes().first() {
+            Some(b'-') => (true, &s[1..]),
+            Some(b'+') => (false, &s[1..]),
+            _ => (false, s),
+        };
+
+        let value = match s.strip_prefix('\'') {
+            Some(chr) => Self::parse_char(chr)
+                .ok_or(format!("Invalid character constant {}.", self.value))?,
+            None => self.parse_integer(s)?,
+        };
+
+        // The magnitude may be 2^63 when the number is negative
+        match negative {
+            true if value <= 1 << 63 => Ok((value as i64).wrapping_neg()),
+            false if value < 1 << 63 => Ok(value as i64),
+            _ => Err(format!("Number {} does not fit in 64 bits.",
+                self.value)),
+        }
+    }
+
+    /// Return true if the number is written with a minus sign, which tells
+    /// `#-0` apart from `#0`
+    pub fn is_negative(&self) -> bool {
+        self.value.strip_prefix('#').unwrap_or(&self.value).starts_with('-')
+    }
+
+    /// Parse the digits of an integer, after its optional radix prefix
+    fn parse_integer(&self, s: &str) -> Result<u64, String> {
+        let invalid = || format!("Invalid number {}.", self.value);
+        let overflow = || format!("Number {} does not fit in 64 bits.",
+            self.value);
+
+        let lower = s.to_ascii_lowercase();
+        let (radix, digits) = if let Some(hex) = lower.strip_prefix("0x") {
+            (16, hex)
+        } else if let Some(bin) = lower.strip_prefix("0b") {
+            (2, bin)
+        } else if let Some(oct) = lower.strip_prefix("0o") {
+            (8, oct)
+        } else if lower.len() > 1 && lower.starts_with('0') {
+            // Leading zero, as in C
+            (8, &lower[1..])
+        } else {
+            (10, lower.as_str())
+        };
+
+        // Digits may be separated by '_'
+        let mut value = 0u64;
+        let mut any = false;
+        for chr in digits.chars().filter(|&chr| chr != '_') {
+            let digit = chr.to_digit(radix).ok_or_else(invalid)?;
+            value = value.checked_mul(radix as u64)
+                .and_then(|value| value.checked_add(digit as u64))
+                .ok_or_else(overflow)?;
+            any = true;
+        }
+
+        match any {
+            true => Ok(value),
+            false => Err(invalid()),
+        }
+    }
+
+    /// Parse a character constant, after its opening quote, with the escape
+    /// sequences of C
+    fn parse_char(s: &str) -> Option<u64> {
+        let s = s.strip_suffix('\'')?;
+        let mut chars = s.chars();
+
+        let value = match chars.next()? {
+            '\\' => match chars.next()? {
+                'n'  => '\n' as u64,
+                't'  => '\t' as u64,
+                'r'  => '\r' as u64,
+                'a'  => 0x07,
+                'b'  => 0x08,
+                'f'  => 0x0c,
+                'v'  => 0x0b,
+                'e'  => 0x1b,
+                '\\' => '\\' as u64,
+                '\'' => '\'' as u64,
+                '"'  => '"' as u64,
+                'x'  => {
+                    let hex: String = chars.by_ref().collect();
+                    return u8::from_str_radix(&hex, 16).ok().map(u64::from);
+                },
+                chr if chr.is_digit(8) => {
+                    let oct: String = std::iter::once(chr).chain(chars.by_ref())
+                        .collect();
+                    return u8::from_str_radix(&oct, 8).ok().map(u64::from);
+                },
+                _    => return None,
+            },
+            chr if chr.is_ascii() => chr as u64,
+            _ => return None,
+        };
+
+        // A single character is allowed between the quotes
+        match chars.next() {
+            Some(_) => None,
+            None => Some(value),
+        }
+    }
+}